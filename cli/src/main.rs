@@ -0,0 +1,248 @@
+use ca_engine::life::*;
+use ca_engine::project::{Project, MAX_SIZE};
+use ca_engine::{rle, rulestring};
+use std::fs;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Runs an automaton without a browser.
+
+Usage: ca-cli [options]
+
+Start from:
+  --project FILE         a saved project (.json)
+  --rule RULE            a rulestring such as B3/S23, when there's no project
+  --pattern FILE         an RLE pattern, centered on a cleared board
+  --size WxH             the board size, by default the pattern's or 64x64
+  --seed N               the seed for rules left to chance, by default the
+                         project's or 0
+
+Run and write:
+  --steps N              how many generations to run, 100 by default
+  --output FILE          the final board, as a project if FILE ends in .json
+                         and as RLE otherwise
+  --populations FILE     a CSV of how many cells are in each state, every generation
+  --snapshot-every N     write the board as RLE every N generations...
+  --snapshot-dir DIR     ...into DIR, '.' by default
+";
+
+const DEFAULT_SIZE: (usize, usize) = (64, 64);
+const DEFAULT_DELAY: u64 = 1000;
+
+#[derive(Default)]
+struct Options {
+    project: Option<String>,
+    rule: Option<String>,
+    pattern: Option<String>,
+    size: Option<(usize, usize)>,
+    seed: Option<u64>,
+    steps: usize,
+    output: Option<String>,
+    populations: Option<String>,
+    snapshot_every: Option<usize>,
+    snapshot_dir: String,
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    match parse_args(&args).and_then(|options| run(&options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        steps: 100,
+        snapshot_dir: String::from("."),
+        ..Default::default()
+    };
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or(format!("{} needs a value", flag))
+        };
+        match flag.as_str() {
+            "--project" => options.project = Some(value()?),
+            "--rule" => options.rule = Some(value()?),
+            "--pattern" => options.pattern = Some(value()?),
+            "--size" => {
+                let size = value()?;
+                let (w, h) = size
+                    .split_once('x')
+                    .ok_or(format!("expected a size like 64x64, got {}", size))?;
+                let (w, h) = (number(w)?, number(h)?);
+                if w > MAX_SIZE || h > MAX_SIZE {
+                    return Err(format!("the board can be at most {0}x{0}", MAX_SIZE));
+                }
+                options.size = Some((w, h));
+            }
+            "--seed" => {
+                let seed = value()?;
+                options.seed = Some(
+                    seed.trim()
+                        .parse()
+                        .map_err(|_| format!("expected a number, got {}", seed))?,
+                );
+            }
+            "--steps" => options.steps = number(&value()?)?,
+            "--output" => options.output = Some(value()?),
+            "--populations" => options.populations = Some(value()?),
+            "--snapshot-every" => options.snapshot_every = Some(number(&value()?)?.max(1)),
+            "--snapshot-dir" => options.snapshot_dir = value()?,
+            _ => return Err(format!("unknown option {}, see --help", flag)),
+        }
+    }
+    if options.project.is_some() && options.rule.is_some() {
+        return Err(String::from("give either --project or --rule, not both"));
+    }
+    Ok(options)
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let (mut board, delay) = load(options)?;
+    if let Some(seed) = options.seed {
+        board.reseed(seed);
+    }
+    // Checked before running, so a long run doesn't end in a project that
+    // can't be opened again
+    let project = options
+        .output
+        .as_ref()
+        .filter(|path| path.ends_with(".json"));
+    if let Some(path) = project {
+        Project::new(&board, delay)
+            .validate()
+            .map_err(|e| format!("can't save {} as a project: {}", path, e))?;
+    }
+    let rule = rulestring::format(&board.state_types, &board.neighborhood).ok();
+    let snapshot = |board: &Board, generation: usize| -> Result<(), String> {
+        match options.snapshot_every {
+            Some(every) if generation.is_multiple_of(every) => {
+                let path = format!("{}/{:06}.rle", options.snapshot_dir, generation);
+                write(&path, &rle::write(&board.grid, rule.as_deref()))
+            }
+            _ => Ok(()),
+        }
+    };
+
+    let mut populations = String::from("generation");
+    for state in 0..board.state_types.len() {
+        populations += &format!(",state {}", state);
+    }
+    let mut record = |board: &Board, generation: usize| {
+        populations += &format!("\n{}", generation);
+        for count in board.population() {
+            populations += &format!(",{}", count);
+        }
+    };
+
+    if options.snapshot_every.is_some() {
+        fs::create_dir_all(&options.snapshot_dir)
+            .map_err(|e| format!("couldn't create {}: {}", options.snapshot_dir, e))?;
+    }
+    record(&board, 0);
+    snapshot(&board, 0)?;
+    for generation in 1..=options.steps {
+        board.step();
+        record(&board, generation);
+        snapshot(&board, generation)?;
+    }
+
+    if let Some(path) = &options.populations {
+        write(path, &(populations + "\n"))?;
+    }
+    match &options.output {
+        Some(path) if path.ends_with(".json") => {
+            write(path, &Project::new(&board, delay).to_json())
+        }
+        Some(path) => write(path, &rle::write(&board.grid, rule.as_deref())),
+        None => Ok(()),
+    }
+}
+
+// Builds the starting board and the delay to save it with
+fn load(options: &Options) -> Result<(Board, u64), String> {
+    let pattern = match &options.pattern {
+        Some(path) => Some(rle::parse(&read(path)?).map_err(|e| format!("{}: {}", path, e))?),
+        None => None,
+    };
+
+    let (mut board, delay) = match &options.project {
+        Some(path) => {
+            let project =
+                Project::from_json(&read(path)?).map_err(|e| format!("{}: {}", path, e))?;
+            let delay = project.delay;
+            let mut board = project.into_board();
+            if let Some((w, h)) = options.size {
+                board.grid = board.grid.resized(w, h, Anchor::TopLeft);
+            }
+            (board, delay)
+        }
+        None => {
+            // The pattern's own rule is used unless one is given
+            let rule = options
+                .rule
+                .clone()
+                .or_else(|| pattern.as_ref().and_then(|p| p.rule.clone()))
+                .unwrap_or_else(|| String::from("B3/S23"));
+            let (states, neighborhood) = rulestring::parse(&rule)?;
+            let (w, h) = options
+                .size
+                .or_else(|| pattern.as_ref().map(|p| (p.width, p.height)))
+                .unwrap_or(DEFAULT_SIZE);
+            let mut board = Board::new(w, h, Some(states));
+            board.neighborhood = neighborhood;
+            (board, DEFAULT_DELAY)
+        }
+    };
+    if board.grid.cells().is_empty() {
+        return Err(String::from("the board must be at least 1x1"));
+    }
+
+    if let Some(pattern) = pattern {
+        let states = board.state_types.len();
+        if let Some(state) = pattern
+            .cells
+            .cells()
+            .iter()
+            .find(|&&c| c as usize >= states)
+        {
+            return Err(format!(
+                "the pattern uses state {} but there are only {} states",
+                state, states
+            ));
+        }
+        let (w, h) = (board.grid.width() as i32, board.grid.height() as i32);
+        board.grid = Grid::new(w as usize, h as usize);
+        board.grid.paste(
+            &pattern.cells,
+            (w - pattern.width as i32) / 2,
+            (h - pattern.height as i32) / 2,
+        );
+    }
+    Ok((board, delay))
+}
+
+fn number(text: &str) -> Result<usize, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("expected a number, got {}", text))
+}
+
+fn read(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {}", path, e))
+}
+
+fn write(path: &str, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("couldn't write {}: {}", path, e))
+}
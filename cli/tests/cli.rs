@@ -0,0 +1,139 @@
+use ca_engine::life::*;
+use ca_engine::project::Project;
+use ca_engine::rle;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const GLIDER: &str = "x = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n";
+
+// An empty directory of its own for each test to read and write in
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ca-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn cli(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ca-cli"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn runs_a_pattern_and_writes_what_it_was_asked_for() {
+    let dir = scratch("run");
+    fs::write(dir.join("glider.rle"), GLIDER).unwrap();
+    let output = cli(
+        &dir,
+        &[
+            "--pattern",
+            "glider.rle",
+            "--size",
+            "8x8",
+            "--steps",
+            "8",
+            "--output",
+            "after.rle",
+            "--populations",
+            "populations.csv",
+            "--snapshot-every",
+            "4",
+            "--snapshot-dir",
+            "snapshots",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+
+    // The glider has moved two cells down and to the right of the middle
+    let after = rle::parse(&fs::read_to_string(dir.join("after.rle")).unwrap()).unwrap();
+    let mut expected = Grid::new(8, 8);
+    expected.paste(&rle::parse(GLIDER).unwrap().cells, 4, 4);
+    assert_eq!(after.cells, expected);
+    assert_eq!(after.rule.as_deref(), Some("B3/S23"));
+
+    let populations = fs::read_to_string(dir.join("populations.csv")).unwrap();
+    let lines = populations.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "generation,state 0,state 1");
+    assert_eq!(lines[8], "7,59,5");
+    assert_eq!(lines.len(), 10);
+    for snapshot in ["000000.rle", "000004.rle", "000008.rle"] {
+        assert!(
+            dir.join("snapshots").join(snapshot).exists(),
+            "{}",
+            snapshot
+        );
+    }
+}
+
+#[test]
+fn converts_between_patterns_and_projects() {
+    let dir = scratch("convert");
+    fs::write(dir.join("glider.rle"), GLIDER).unwrap();
+    let to_project = [
+        "--pattern",
+        "glider.rle",
+        "--steps",
+        "0",
+        "--output",
+        "glider.json",
+    ];
+    assert!(cli(&dir, &to_project).status.success());
+    let project = Project::from_json(&fs::read_to_string(dir.join("glider.json")).unwrap());
+    let board = project.unwrap().into_board();
+    assert_eq!(board.grid, rle::parse(GLIDER).unwrap().cells);
+
+    let to_pattern = [
+        "--project",
+        "glider.json",
+        "--steps",
+        "0",
+        "--output",
+        "back.rle",
+    ];
+    assert!(cli(&dir, &to_pattern).status.success());
+    let back = rle::parse(&fs::read_to_string(dir.join("back.rle")).unwrap()).unwrap();
+    assert_eq!(back.cells, board.grid);
+}
+
+#[test]
+fn bad_runs_fail_with_a_message() {
+    let dir = scratch("errors");
+    fs::write(dir.join("glider.rle"), GLIDER).unwrap();
+    fs::write(dir.join("wide.rle"), "x = 2000, y = 1\n2000o!").unwrap();
+    for (args, message) in [
+        (vec!["--frobnicate"], "unknown option"),
+        (vec!["--steps"], "needs a value"),
+        (vec!["--steps", "many"], "expected a number"),
+        (vec!["--size", "8by8"], "expected a size"),
+        (vec!["--rule", "B3/S23", "--project", "x.json"], "not both"),
+        (vec!["--rule", "B9/S23"], "more than the 8 cells"),
+        (vec!["--pattern", "missing.rle"], "couldn't read"),
+        (vec!["--size", "0x8"], "at least 1x1"),
+        (vec!["--size", "1000000x1000000"], "at most 1024x1024"),
+        (
+            vec!["--size", "2000x8", "--steps", "0", "--output", "wide.json"],
+            "at most",
+        ),
+        (
+            vec![
+                "--pattern",
+                "wide.rle",
+                "--steps",
+                "0",
+                "--output",
+                "wide.json",
+            ],
+            "can't save",
+        ),
+    ] {
+        let output = cli(&dir, &args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(stderr.contains(message), "{:?}: {}", args, stderr);
+    }
+    assert!(!dir.join("wide.json").exists());
+}
@@ -0,0 +1,131 @@
+use crate::life::*;
+
+// A two state rule on the 3x3 Moore neighborhood, as bit masks of the live
+// neighbor counts that give birth to a dead cell and keep a live one alive,
+// e.g. Game of Life is births 1 << 3 and survivals 1 << 2 | 1 << 3
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Masks {
+    birth: u16,
+    survival: u16,
+}
+
+impl Masks {
+    pub fn next(&self, alive: bool, neighbors: u32) -> bool {
+        let mask = if alive { self.survival } else { self.birth };
+        mask >> neighbors & 1 == 1
+    }
+}
+
+// With only two states the dead count is 8 minus the live count, so any rules
+// the board has can be read back as masks by trying every live count. Rules
+// left to chance can't be.
+pub fn life_masks(board: &Board) -> Option<Masks> {
+    let states = &board.state_types;
+    if states.len() != 2
+        || board.neighborhood != Neighborhood::Moore(1)
+        || !board.is_deterministic()
+    {
+        return None;
+    }
+    for (i, state) in states.iter().enumerate() {
+        let targets = state.rules.iter().map(|rule| rule.target_state);
+        let counted = state
+            .rules
+            .iter()
+            .flat_map(|rule| rule.condition.count_states());
+        if state.index != i || state.fail_state > 1 || targets.chain(counted).any(|state| state > 1)
+        {
+            return None;
+        }
+    }
+
+    let mut masks = Masks {
+        birth: 0,
+        survival: 0,
+    };
+    for alive in 0..=8 {
+        let counts = [8 - alive, alive];
+        if board.transition(0, &counts) == 1 {
+            masks.birth |= 1 << alive;
+        }
+        if board.transition(1, &counts) == 1 {
+            masks.survival |= 1 << alive;
+        }
+    }
+    Some(masks)
+}
+
+// Steps 64 cells at a time. Each row is packed into bits with a ghost cell at
+// either end, and the rows above and below the board are ghosts too, all read
+// through the board's boundaries.
+pub fn step(board: &Board, masks: Masks, next: &mut Grid) {
+    let (width, height) = (board.grid.width(), board.grid.height());
+    let words = (width + 2).div_ceil(64);
+    let pack = |y: i32| {
+        let mut row = vec![0u64; words];
+        for i in 0..width + 2 {
+            let x = i as i32 - 1;
+            let alive = match (0..height as i32).contains(&y) && (0..width as i32).contains(&x) {
+                true => board.grid.get(x as usize, y as usize) == 1,
+                false => board.cell_at(x, y) == 1,
+            };
+            if alive {
+                row[i / 64] |= 1 << (i % 64);
+            }
+        }
+        row
+    };
+    // Moves every cell one bit along, carrying across words
+    let west = |row: &[u64], w: usize| row[w] << 1 | if w > 0 { row[w - 1] >> 63 } else { 0 };
+    let east =
+        |row: &[u64], w: usize| row[w] >> 1 | if w + 1 < words { row[w + 1] << 63 } else { 0 };
+
+    let counts = |masks: u16| (0..=8).filter(move |n| masks >> n & 1 == 1);
+    let (mut above, mut current) = (pack(-1), pack(0));
+    let mut result = vec![0u64; words];
+    for y in 0..height {
+        let below = pack(y as i32 + 1);
+        for (w, word) in result.iter_mut().enumerate() {
+            let neighbors = [
+                west(&above, w),
+                above[w],
+                east(&above, w),
+                west(&current, w),
+                east(&current, w),
+                west(&below, w),
+                below[w],
+                east(&below, w),
+            ];
+            let sum = add(neighbors);
+            let equals = |n: u32| {
+                (0..4).fold(!0, |m, bit| match n >> bit & 1 {
+                    1 => m & sum[bit],
+                    _ => m & !sum[bit],
+                })
+            };
+            let born = counts(masks.birth).fold(0, |m, n| m | equals(n));
+            let survive = counts(masks.survival).fold(0, |m, n| m | equals(n));
+            *word = !current[w] & born | current[w] & survive;
+        }
+
+        let row = &mut next.cells_mut()[y * width..(y + 1) * width];
+        for (x, cell) in row.iter_mut().enumerate() {
+            let i = x + 1;
+            *cell = (result[i / 64] >> (i % 64) & 1) as Cell;
+        }
+        above = std::mem::replace(&mut current, below);
+    }
+}
+
+// Adds eight one bit numbers in every bit position at once, giving the four
+// bits of each sum from lowest to highest
+fn add(n: [u64; 8]) -> [u64; 4] {
+    let full = |a: u64, b: u64, c: u64| (a ^ b ^ c, a & b | c & (a ^ b));
+    let (ones_a, twos_a) = full(n[0], n[1], n[2]);
+    let (ones_b, twos_b) = full(n[3], n[4], n[5]);
+    let (ones_c, twos_c) = (n[6] ^ n[7], n[6] & n[7]);
+    let (ones, twos_d) = full(ones_a, ones_b, ones_c);
+    let (twos_e, fours_a) = full(twos_a, twos_b, twos_c);
+    let (twos, fours_b) = (twos_e ^ twos_d, twos_e & twos_d);
+    [ones, twos, fours_a ^ fours_b, fours_a & fours_b]
+}
@@ -0,0 +1,146 @@
+use crate::life::*;
+use std::collections::HashSet;
+
+// What dragging across the board draws
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tool {
+    Freehand,
+    Line,
+    Rectangle,
+    FilledRectangle,
+    Circle,
+    FilledCircle,
+    Fill,
+    Select, // marks out a rectangle instead of painting
+    Stamp,  // places a pattern from the library
+}
+
+type Point = (i64, i64);
+
+impl Tool {
+    // The cells a drag from one point to another covers, before the brush
+    // size is applied. Freehand strokes are drawn as lines between successive
+    // mouse positions, and flood fills, selections and stamps don't have a shape.
+    pub fn shape(&self, from: Point, to: Point) -> Vec<Point> {
+        match self {
+            Tool::Freehand | Tool::Line => line(from, to),
+            Tool::Rectangle => rectangle(from, to, false),
+            Tool::FilledRectangle => rectangle(from, to, true),
+            Tool::Circle => circle(from, to, false),
+            Tool::FilledCircle => circle(from, to, true),
+            Tool::Fill | Tool::Select | Tool::Stamp => vec![],
+        }
+    }
+}
+
+// Bresenham's line, including both ends
+pub fn line(from: Point, to: Point) -> Vec<Point> {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+    let mut points = vec![];
+    loop {
+        points.push((x, y));
+        if (x, y) == to {
+            return points;
+        }
+        if 2 * error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if 2 * error <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+// The rectangle with opposite corners at the two points
+pub fn rectangle(from: Point, to: Point, filled: bool) -> Vec<Point> {
+    let (left, right) = (from.0.min(to.0), from.0.max(to.0));
+    let (top, bottom) = (from.1.min(to.1), from.1.max(to.1));
+    let mut points = vec![];
+    for y in top..=bottom {
+        for x in left..=right {
+            if filled || x == left || x == right || y == top || y == bottom {
+                points.push((x, y));
+            }
+        }
+    }
+    points
+}
+
+// The circle centered on one point passing through the other. Cells count as
+// inside when they're within half a cell of the radius, which keeps the outline
+// one cell thick without gaps.
+pub fn circle(center: Point, edge: Point, filled: bool) -> Vec<Point> {
+    let (dx, dy) = ((edge.0 - center.0) as f64, (edge.1 - center.1) as f64);
+    let r = (dx * dx + dy * dy).sqrt().round() as i64;
+    let mut points = vec![];
+    for y in -r..=r {
+        for x in -r..=r {
+            let d = x * x + y * y;
+            if d <= r * r + r && (filled || r == 0 || d > r * r - r) {
+                points.push((center.0 + x, center.1 + y));
+            }
+        }
+    }
+    points
+}
+
+// Paints a square brush size cells across onto each point, dropping whatever
+// falls off the grid
+pub fn paint(grid: &mut Grid, points: &[Point], size: usize, state: usize) {
+    let size = size.max(1) as i64;
+    let low = -(size - 1) / 2;
+    let (width, height) = (grid.width() as i64, grid.height() as i64);
+    let mut set = |x: i64, y: i64| {
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            grid.set(x as usize, y as usize, state);
+        }
+    };
+    // Inside a filled shape the brush only covers what the shape's edge does,
+    // so only points on the edge get the whole square
+    let shape = points.iter().copied().collect::<HashSet<_>>();
+    let on_edge =
+        |&(x, y): &Point| (-1..=1).any(|dy| (-1..=1).any(|dx| !shape.contains(&(x + dx, y + dy))));
+    for &(x, y) in points {
+        if size == 1 || !on_edge(&(x, y)) {
+            set(x, y);
+            continue;
+        }
+        for dy in low..low + size {
+            for dx in low..low + size {
+                set(x + dx, y + dy);
+            }
+        }
+    }
+}
+
+// Paints the cell and every cell in the same state joined to it by an edge
+pub fn flood_fill(grid: &mut Grid, x: usize, y: usize, state: usize) {
+    let target = grid.get(x, y);
+    if target == state {
+        return;
+    }
+    let mut stack = vec![(x, y)];
+    while let Some((x, y)) = stack.pop() {
+        if grid.get(x, y) != target {
+            continue;
+        }
+        grid.set(x, y, state);
+        if x > 0 {
+            stack.push((x - 1, y));
+        }
+        if y > 0 {
+            stack.push((x, y - 1));
+        }
+        if x + 1 < grid.width() {
+            stack.push((x + 1, y));
+        }
+        if y + 1 < grid.height() {
+            stack.push((x, y + 1));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::life::{Cell, Grid};
+
+pub const MAX_COLORS: usize = 8;
+pub const MAX_RADIUS: usize = 3;
+
+// How a row of cells works out its next generation. Rows wrap around, so the
+// cells at either end are neighbours.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    // One of Wolfram's 256 two color rules, where bit l*4 + c*2 + r of the
+    // number is the next state of a cell c between l and r
+    Elementary(u8),
+    // The next state of a cell is the digit of the code, written in base
+    // colors, at the sum of the cells within radius of it
+    Totalistic {
+        colors: usize,
+        radius: usize,
+        code: u64,
+    },
+}
+
+impl Rule {
+    pub fn totalistic(colors: usize, radius: usize, code: u64) -> Result<Self, String> {
+        if !(2..=MAX_COLORS).contains(&colors) {
+            return Err(format!("colors go from 2 to {}", MAX_COLORS));
+        }
+        if !(1..=MAX_RADIUS).contains(&radius) {
+            return Err(format!("the radius goes from 1 to {}", MAX_RADIUS));
+        }
+        let sums = (2 * radius + 1) * (colors - 1) + 1;
+        let codes = (colors as u64).checked_pow(sums as u32);
+        if codes.is_some_and(|codes| code >= codes) {
+            return Err(format!(
+                "codes for {} colors and radius {} go up to {}",
+                colors,
+                radius,
+                codes.unwrap() - 1
+            ));
+        }
+        Ok(Rule::Totalistic {
+            colors,
+            radius,
+            code,
+        })
+    }
+
+    pub fn colors(&self) -> usize {
+        match self {
+            Rule::Elementary(_) => 2,
+            Rule::Totalistic { colors, .. } => *colors,
+        }
+    }
+
+    // The next state for every neighbourhood, indexed the way step looks it up
+    fn table(&self) -> Vec<Cell> {
+        match *self {
+            Rule::Elementary(number) => (0..8).map(|i| ((number >> i) & 1) as Cell).collect(),
+            Rule::Totalistic {
+                colors,
+                radius,
+                mut code,
+            } => {
+                let sums = (2 * radius + 1) * (colors - 1) + 1;
+                (0..sums)
+                    .map(|_| {
+                        let digit = code % colors as u64;
+                        code /= colors as u64;
+                        digit as Cell
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub fn step(&self, row: &[Cell]) -> Vec<Cell> {
+        let n = row.len() as i64;
+        let table = self.table();
+        let at = |i: i64| row[i.rem_euclid(n) as usize] as usize;
+        (0..n)
+            .map(|i| match *self {
+                Rule::Elementary(_) => table[at(i - 1) * 4 + at(i) * 2 + at(i + 1)],
+                Rule::Totalistic { radius, .. } => {
+                    let r = radius as i64;
+                    table[(i - r..=i + r).map(at).sum::<usize>()]
+                }
+            })
+            .collect()
+    }
+}
+
+// A row and the generations before it, one per row of the grid with the
+// newest at the bottom. Once the grid fills up the oldest row drops off the
+// top, so the picture scrolls.
+pub struct Spacetime {
+    pub rule: Rule,
+    pub generation: usize,
+    grid: Grid,
+    rows: usize,
+}
+
+impl Spacetime {
+    pub fn new(rule: Rule, first: &[Cell], height: usize) -> Self {
+        let mut grid = Grid::new(first.len(), height.max(1));
+        grid.cells_mut()[..first.len()].copy_from_slice(first);
+        Spacetime {
+            rule,
+            generation: 0,
+            grid,
+            rows: 1,
+        }
+    }
+
+    pub fn row(&self) -> &[Cell] {
+        let width = self.grid.width();
+        &self.grid.cells()[(self.rows - 1) * width..self.rows * width]
+    }
+
+    // The generations so far. Rows below the newest are empty until it
+    // scrolls.
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    // Rows of the grid holding a generation
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn step(&mut self) {
+        let next = self.rule.step(self.row());
+        let width = self.grid.width();
+        if self.rows == self.grid.height() {
+            let cells = self.grid.cells_mut();
+            cells.copy_within(width.., 0);
+        } else {
+            self.rows += 1;
+        }
+        let start = (self.rows - 1) * width;
+        self.grid.cells_mut()[start..start + width].copy_from_slice(&next);
+        self.generation += 1;
+    }
+}
+
+// A row with one cell set in the middle
+pub fn single_cell(width: usize) -> Vec<Cell> {
+    let mut row = vec![0; width];
+    if width > 0 {
+        row[width / 2] = 1;
+    }
+    row
+}
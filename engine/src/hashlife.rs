@@ -0,0 +1,221 @@
+use crate::bitwise::{self, Masks};
+use crate::life::*;
+use std::collections::HashMap;
+
+// The largest jump is 2^MAX_JUMP generations
+pub const MAX_JUMP: u32 = 48;
+
+type Id = u32;
+const DEAD: Id = 0;
+const ALIVE: Id = 1;
+
+// Gosper's HashLife. The plane is a quadtree whose identical squares are
+// stored once, and the future of every square is remembered, so repetitive
+// patterns can be run for huge numbers of generations.
+struct HashLife {
+    masks: Masks,
+    // The nw, ne, sw and se quarters of each square; 0 and 1 are single cells
+    children: Vec<[Id; 4]>,
+    levels: Vec<u8>,
+    squares: HashMap<[Id; 4], Id>,
+    // The center half of a square after 2^j generations, by (square, j)
+    futures: HashMap<(Id, u8), Id>,
+}
+
+impl HashLife {
+    fn new(masks: Masks) -> Self {
+        HashLife {
+            masks,
+            children: vec![[DEAD; 4], [ALIVE; 4]],
+            levels: vec![0, 0],
+            squares: HashMap::new(),
+            futures: HashMap::new(),
+        }
+    }
+
+    fn join(&mut self, quarters: [Id; 4]) -> Id {
+        if let Some(&id) = self.squares.get(&quarters) {
+            return id;
+        }
+        let id = self.children.len() as Id;
+        self.children.push(quarters);
+        self.levels.push(self.levels[quarters[0] as usize] + 1);
+        self.squares.insert(quarters, id);
+        id
+    }
+
+    fn quarters(&self, id: Id) -> [Id; 4] {
+        self.children[id as usize]
+    }
+
+    fn level(&self, id: Id) -> u8 {
+        self.levels[id as usize]
+    }
+
+    fn cell(&self, id: Id, x: u64, y: u64) -> bool {
+        let level = self.level(id);
+        if level == 0 {
+            return id == ALIVE;
+        }
+        let half = 1u64 << (level - 1);
+        let quarter = (x >= half) as usize + 2 * (y >= half) as usize;
+        self.cell(self.quarters(id)[quarter], x % half, y % half)
+    }
+
+    // The square made of the inner quarters of a square's quarters
+    fn centre(&mut self, id: Id) -> Id {
+        let [nw, ne, sw, se] = self.quarters(id);
+        let quarters = [
+            self.quarters(nw)[3],
+            self.quarters(ne)[2],
+            self.quarters(sw)[1],
+            self.quarters(se)[0],
+        ];
+        self.join(quarters)
+    }
+
+    // The center half of a square of size 2^level after 2^j generations,
+    // where j is at most level - 2
+    fn future(&mut self, id: Id, j: u8) -> Id {
+        if let Some(&future) = self.futures.get(&(id, j)) {
+            return future;
+        }
+        let level = self.level(id);
+        let future = if level == 2 {
+            self.future_of_four(id)
+        } else {
+            // Nine overlapping squares of half the size, row by row
+            let [a, b, c, d] = self.quarters(id);
+            let [_, a_ne, a_sw, a_se] = self.quarters(a);
+            let [b_nw, _, b_sw, b_se] = self.quarters(b);
+            let [c_nw, c_ne, _, c_se] = self.quarters(c);
+            let [d_nw, d_ne, d_sw, _] = self.quarters(d);
+            let nine = [
+                a,
+                self.join([a_ne, b_nw, a_se, b_sw]),
+                b,
+                self.join([a_sw, a_se, c_nw, c_ne]),
+                self.join([a_se, b_sw, c_ne, d_nw]),
+                self.join([b_sw, b_se, d_nw, d_ne]),
+                c,
+                self.join([c_ne, d_nw, c_se, d_sw]),
+                d,
+            ];
+            // At full speed both halves of the trip move 2^(level - 3)
+            // generations, otherwise only the second moves at all
+            let full = j + 2 == level;
+            let half_j = if full { level - 3 } else { j };
+            let mut r = [DEAD; 9];
+            for (r, &square) in r.iter_mut().zip(&nine) {
+                *r = match full {
+                    true => self.future(square, level - 3),
+                    false => self.centre(square),
+                };
+            }
+            let mut quarters = [DEAD; 4];
+            let corners = [[0, 1, 3, 4], [1, 2, 4, 5], [3, 4, 6, 7], [4, 5, 7, 8]];
+            for (quarter, corner) in quarters.iter_mut().zip(corners) {
+                let square = self.join(corner.map(|i| r[i]));
+                *quarter = self.future(square, half_j);
+            }
+            self.join(quarters)
+        };
+        self.futures.insert((id, j), future);
+        future
+    }
+
+    // The smallest case, a 4x4 square whose center 2x2 moves one generation
+    fn future_of_four(&mut self, id: Id) -> Id {
+        let alive = |x: i32, y: i32| self.cell(id, x as u64, y as u64);
+        let mut quarters = [DEAD; 4];
+        for (i, quarter) in quarters.iter_mut().enumerate() {
+            let (x, y) = (1 + i as i32 % 2, 1 + i as i32 / 2);
+            let mut neighbors = 0;
+            for (dx, dy) in Neighborhood::Moore(1).offsets() {
+                neighbors += alive(x + dx, y + dy) as u32;
+            }
+            if self.masks.next(alive(x, y), neighbors) {
+                *quarter = ALIVE;
+            }
+        }
+        self.join(quarters)
+    }
+}
+
+// Moves a board on 2^log2 generations at once. The board must wrap on both
+// axes, which HashLife sees as the board tiled across the whole plane, and its
+// rules must be deterministic two state rules on the Moore neighborhood.
+pub fn jump(board: &mut Board, log2: u32) -> Result<(), String> {
+    let masks = bitwise::life_masks(board)
+        .ok_or("jumps need two states without chance and a radius 1 Moore neighborhood")?;
+    if board.boundary_x != Boundary::Wrap || board.boundary_y != Boundary::Wrap {
+        return Err(String::from("jumps need both edges to wrap"));
+    }
+    if log2 > MAX_JUMP {
+        return Err(format!("jumps can be at most 2^{} generations", MAX_JUMP));
+    }
+    let (width, height) = (board.grid.width(), board.grid.height());
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+
+    // The future of a square is its center half, so the square has to be big
+    // enough for that half to cover the board, and at least 4 times the jump
+    let cover = usize::BITS - (width.max(height) - 1).leading_zeros() + 1;
+    let level = (log2 + 2).max(cover).max(2);
+    let mut life = HashLife::new(masks);
+    let mut tiles = HashMap::new();
+    // Starting a quarter of the way out puts the center half's corner on (0, 0)
+    let origin = -(1i64 << (level - 2));
+    let root = tile(
+        &mut life,
+        &mut tiles,
+        &board.grid,
+        level as u8,
+        origin,
+        origin,
+    );
+    let future = life.future(root, log2 as u8);
+
+    for y in 0..height {
+        for x in 0..width {
+            board
+                .grid
+                .set(x, y, life.cell(future, x as u64, y as u64) as usize);
+        }
+    }
+    Ok(())
+}
+
+// Builds the square of size 2^level at (x, y) on the plane tiled with the
+// grid. Squares at the same place within the tiling are the same square.
+fn tile(
+    life: &mut HashLife,
+    tiles: &mut HashMap<(u8, usize, usize), Id>,
+    grid: &Grid,
+    level: u8,
+    x: i64,
+    y: i64,
+) -> Id {
+    let (x, y) = (
+        x.rem_euclid(grid.width() as i64),
+        y.rem_euclid(grid.height() as i64),
+    );
+    let key = (level, x as usize, y as usize);
+    if let Some(&id) = tiles.get(&key) {
+        return id;
+    }
+    let id = if level == 0 {
+        (grid.get(key.1, key.2) == 1) as Id
+    } else {
+        let half = 1i64 << (level - 1);
+        let mut quarters = [DEAD; 4];
+        for (i, quarter) in quarters.iter_mut().enumerate() {
+            let (dx, dy) = ((i % 2) as i64 * half, (i / 2) as i64 * half);
+            *quarter = tile(life, tiles, grid, level - 1, x + dx, y + dy);
+        }
+        life.join(quarters)
+    };
+    tiles.insert(key, id);
+    id
+}
@@ -0,0 +1,206 @@
+use crate::life::*;
+use std::collections::VecDeque;
+use std::mem::{size_of, size_of_val};
+
+pub const DEFAULT_BUDGET: usize = 4 * 1024 * 1024;
+
+// The parts of a board that describe how it evolves rather than what is on it
+#[derive(Clone, PartialEq)]
+pub struct Rules {
+    pub state_types: Vec<State>,
+    pub neighborhood: Neighborhood,
+    pub boundary_x: Boundary,
+    pub boundary_y: Boundary,
+}
+
+// One undo step. Painting only keeps the cells that changed as (x, y, before,
+// after), while anything that changes the size of the grid keeps all of it.
+#[derive(Clone)]
+struct Edit {
+    cells: Vec<(usize, usize, usize, usize)>,
+    grid: Option<(Grid, Grid)>,
+    rules: Option<(Rules, Rules)>,
+}
+
+// Undo and redo stacks of board edits. The oldest edits are forgotten once the
+// stacks take up more than budget bytes.
+pub struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    budget: usize,
+    used: usize,
+}
+
+impl Rules {
+    pub fn of(board: &Board) -> Self {
+        Rules {
+            state_types: board.state_types.clone(),
+            neighborhood: board.neighborhood.clone(),
+            boundary_x: board.boundary_x.clone(),
+            boundary_y: board.boundary_y.clone(),
+        }
+    }
+
+    fn apply(&self, board: &mut Board) {
+        board.state_types = self.state_types.clone();
+        board.neighborhood = self.neighborhood.clone();
+        board.boundary_x = self.boundary_x.clone();
+        board.boundary_y = self.boundary_y.clone();
+    }
+
+    // Only a rough guess, since rules are small next to grids
+    fn size(&self) -> usize {
+        let rules: usize = self.state_types.iter().map(|s| s.rules.len()).sum();
+        size_of::<Self>() + self.state_types.len() * size_of::<State>() + rules * size_of::<Rule>()
+    }
+}
+
+impl Edit {
+    fn size(&self) -> usize {
+        let grid = |grid: &Grid| size_of_val(grid.cells());
+        size_of::<Self>()
+            + self.cells.len() * size_of::<(usize, usize, usize, usize)>()
+            + self.grid.as_ref().map_or(0, |(a, b)| grid(a) + grid(b))
+            + self.rules.as_ref().map_or(0, |(a, b)| a.size() + b.size())
+    }
+
+    fn undo(&self, board: &mut Board) {
+        if let Some((before, _)) = &self.rules {
+            before.apply(board);
+        }
+        if let Some((before, _)) = &self.grid {
+            board.grid = before.clone();
+        }
+        for &(x, y, before, _) in &self.cells {
+            set(&mut board.grid, x, y, before);
+        }
+    }
+
+    fn redo(&self, board: &mut Board) {
+        if let Some((_, after)) = &self.rules {
+            after.apply(board);
+        }
+        if let Some((_, after)) = &self.grid {
+            board.grid = after.clone();
+        }
+        for &(x, y, _, after) in &self.cells {
+            set(&mut board.grid, x, y, after);
+        }
+    }
+}
+
+impl History {
+    pub fn new(budget: usize) -> Self {
+        History {
+            undo: VecDeque::new(),
+            redo: vec![],
+            budget,
+            used: 0,
+        }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    // Roughly how many bytes the steps take up
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.trim();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // Forgets every step, for when the board has moved under them
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.used = 0;
+    }
+
+    // Records everything that differs between two versions of a board as a
+    // single undo step, and forgets anything that could have been redone
+    pub fn record(&mut self, before: &Board, after: &Board) {
+        let rules = Rules::of(before);
+        let rules = (rules != Rules::of(after)).then(|| (rules, Rules::of(after)));
+        let (width, height) = (after.grid.width(), after.grid.height());
+        let same_size = before.grid.width() == width && before.grid.height() == height;
+        let mut cells = vec![];
+        if same_size {
+            for y in 0..height {
+                for x in 0..width {
+                    let (old, new) = (before.grid.get(x, y), after.grid.get(x, y));
+                    if old != new {
+                        cells.push((x, y, old, new));
+                    }
+                }
+            }
+        }
+        let grid = (!same_size).then(|| (before.grid.clone(), after.grid.clone()));
+        if cells.is_empty() && grid.is_none() && rules.is_none() {
+            return;
+        }
+
+        let edit = Edit { cells, grid, rules };
+        self.used += edit.size();
+        self.undo.push_back(edit);
+        self.used -= self.redo.drain(..).map(|e| e.size()).sum::<usize>();
+        self.trim();
+    }
+
+    // Both return false when there was nothing to undo or redo. Only the cells
+    // an edit changed are put back, so undoing after the simulation has run
+    // leaves every other cell as the run left it.
+    pub fn undo(&mut self, board: &mut Board) -> bool {
+        match self.undo.pop_back() {
+            Some(edit) => {
+                edit.undo(board);
+                self.redo.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self, board: &mut Board) -> bool {
+        match self.redo.pop() {
+            Some(edit) => {
+                edit.redo(board);
+                self.undo.push_back(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Drops redo steps first, then the oldest undo steps
+    fn trim(&mut self) {
+        while self.used > self.budget && !self.redo.is_empty() {
+            self.used -= self.redo.remove(0).size();
+        }
+        while self.used > self.budget {
+            match self.undo.pop_front() {
+                Some(edit) => self.used -= edit.size(),
+                None => break,
+            }
+        }
+    }
+}
+
+// Cells recorded on a grid that has since been swapped for a smaller one are
+// left alone
+fn set(grid: &mut Grid, x: usize, y: usize, state: usize) {
+    if x < grid.width() && y < grid.height() {
+        grid.set(x, y, state);
+    }
+}
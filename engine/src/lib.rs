@@ -0,0 +1,17 @@
+// The simulation and file formats, kept free of the browser so they can be
+// tested and reused natively
+mod bitwise;
+pub mod brush;
+pub mod elementary;
+pub mod hashlife;
+pub mod history;
+pub mod life;
+pub mod patterns;
+pub mod presets;
+pub mod project;
+pub mod random;
+pub mod rle;
+pub mod rulestring;
+pub mod timeline;
+pub mod universe;
+pub mod viewport;
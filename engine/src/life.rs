@@ -0,0 +1,675 @@
+use crate::bitwise;
+use crate::random::Random;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Comparisons a rule can make against a neighbor count. Ranges are inclusive.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Predicate {
+    Equal(u32),
+    NotEqual(u32),
+    Less(u32),
+    LessEqual(u32),
+    Greater(u32),
+    GreaterEqual(u32),
+    InRange(u32, u32),
+    InSet(Vec<u32>),
+}
+
+// A test on the neighbor counts of a cell. Leaves compare the count of a single
+// state, and can be combined into trees with And, Or and Not.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    Count { state: usize, predicate: Predicate },
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+// If the condition holds, go to target_state with the given probability, else
+// try the next rule and finally the state's fail state
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub target_state: usize,
+    pub condition: Condition,
+    #[serde(default = "certain")]
+    pub probability: f64,
+}
+
+// When none of its rules fire, a cell goes to the fail state with the fail
+// probability and otherwise stays as it is
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    pub index: usize,
+    pub color: String,
+    pub fail_state: usize,
+    #[serde(default = "certain")]
+    pub fail_probability: f64,
+    pub rules: Vec<Rule>,
+}
+
+// Projects saved before rules had probabilities always took them
+fn certain() -> f64 {
+    1.0
+}
+
+// The set of cells counted as neighbors, as offsets from the center cell.
+// Radii are measured in cells, so Moore(1) is the classic 3x3 block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Neighborhood {
+    Moore(u32),
+    VonNeumann(u32),
+    Cross(u32),
+    Custom(Vec<(i32, i32)>),
+}
+
+// What neighbor lookups see past an edge of the board, chosen separately for
+// each axis. Wrapping one axis and killing the other gives a cylinder, twisting
+// one axis and wrapping the other gives a Klein bottle.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Boundary {
+    Wrap,         // continue on the opposite edge
+    Twist,        // continue on the opposite edge, mirrored along the other axis
+    Reflect,      // mirror the board back across the edge
+    Dead,         // everything outside is state 0
+    State(usize), // everything outside is the given state
+}
+
+// The furthest a neighborhood reaches, and the most offsets a custom one can
+// list, which keep a step from taking forever
+pub const MAX_RADIUS: u32 = 16;
+pub const MAX_OFFSETS: usize = 1088;
+
+// A cell's state. Two bytes leaves room for far more states than anyone
+// will edit by hand while keeping large grids small.
+pub type Cell = u16;
+
+// The cells of a board, stored row after row in one buffer
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+// Which part of a grid stays put when it's resized
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Center,
+}
+
+#[derive(Clone)]
+pub struct Board {
+    pub grid: Grid,
+    pub state_types: Vec<State>,
+    pub neighborhood: Neighborhood,
+    pub boundary_x: Boundary,
+    pub boundary_y: Boundary,
+    // Rolls the dice for rules with probabilities, so a run can be repeated
+    // from the same seed
+    pub random: Random,
+    // The next generation is written here and then swapped with grid, so
+    // stepping doesn't allocate
+    back: Grid,
+}
+
+impl Predicate {
+    pub fn test(&self, count: u32) -> bool {
+        match self {
+            Predicate::Equal(n) => count == *n,
+            Predicate::NotEqual(n) => count != *n,
+            Predicate::Less(n) => count < *n,
+            Predicate::LessEqual(n) => count <= *n,
+            Predicate::Greater(n) => count > *n,
+            Predicate::GreaterEqual(n) => count >= *n,
+            Predicate::InRange(min, max) => (*min..=*max).contains(&count),
+            Predicate::InSet(set) => set.contains(&count),
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Predicate::Equal(n) => write!(f, "== {}", n),
+            Predicate::NotEqual(n) => write!(f, "!= {}", n),
+            Predicate::Less(n) => write!(f, "< {}", n),
+            Predicate::LessEqual(n) => write!(f, "<= {}", n),
+            Predicate::Greater(n) => write!(f, "> {}", n),
+            Predicate::GreaterEqual(n) => write!(f, ">= {}", n),
+            Predicate::InRange(min, max) => write!(f, "in {}..={}", min, max),
+            Predicate::InSet(set) => {
+                let set = set.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+                write!(f, "in {{{}}}", set.join(", "))
+            }
+        }
+    }
+}
+
+impl Condition {
+    pub fn count(state: usize, predicate: Predicate) -> Self {
+        Condition::Count { state, predicate }
+    }
+
+    // counts holds the number of neighbors in each state, indexed by state
+    pub fn test(&self, counts: &[u32]) -> bool {
+        match self {
+            Condition::Count { state, predicate } => predicate.test(counts[*state]),
+            Condition::And(conditions) => conditions.iter().all(|c| c.test(counts)),
+            Condition::Or(conditions) => conditions.iter().any(|c| c.test(counts)),
+            Condition::Not(condition) => !condition.test(counts),
+        }
+    }
+
+    // Every state whose count the condition looks at, in tree order
+    pub fn count_states(&self) -> Vec<usize> {
+        match self {
+            Condition::Count { state, .. } => vec![*state],
+            Condition::And(conditions) | Condition::Or(conditions) => {
+                conditions.iter().flat_map(|c| c.count_states()).collect()
+            }
+            Condition::Not(condition) => condition.count_states(),
+        }
+    }
+
+    pub fn count_states_mut(&mut self) -> Vec<&mut usize> {
+        match self {
+            Condition::Count { state, .. } => vec![state],
+            Condition::And(conditions) | Condition::Or(conditions) => conditions
+                .iter_mut()
+                .flat_map(|c| c.count_states_mut())
+                .collect(),
+            Condition::Not(condition) => condition.count_states_mut(),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |conditions: &[Condition], op: &str| {
+            let conditions = conditions.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            format!("({})", conditions.join(op))
+        };
+        match self {
+            Condition::Count { state, predicate } => write!(f, "#{} {}", state, predicate),
+            Condition::And(conditions) => write!(f, "{}", join(conditions, " and ")),
+            Condition::Or(conditions) => write!(f, "{}", join(conditions, " or ")),
+            Condition::Not(condition) => write!(f, "not {}", condition),
+        }
+    }
+}
+
+impl Rule {
+    pub fn new(
+        target_state: usize,  // state to go to if the condition holds
+        condition: Condition, // test on the neighbor counts
+    ) -> Self {
+        Rule {
+            target_state,
+            condition,
+            probability: 1.0,
+        }
+    }
+
+    pub fn with_probability(mut self, probability: f64) -> Self {
+        self.probability = probability;
+        self
+    }
+}
+
+impl State {
+    pub fn new(index: usize, color: String, fail_state: usize, rules: Vec<Rule>) -> Self {
+        State {
+            index,
+            color,
+            fail_state,
+            fail_probability: 1.0,
+            rules,
+        }
+    }
+
+    pub fn with_fail_probability(mut self, probability: f64) -> Self {
+        self.fail_probability = probability;
+        self
+    }
+}
+
+impl Neighborhood {
+    // Fails for neighborhoods too big to step, or that count the cell itself
+    pub fn validate(&self) -> Result<(), String> {
+        let offsets = match self {
+            Neighborhood::Moore(r) | Neighborhood::VonNeumann(r) | Neighborhood::Cross(r) => {
+                return match *r <= MAX_RADIUS {
+                    true => Ok(()),
+                    false => Err(format!("the radius can be at most {}", MAX_RADIUS)),
+                };
+            }
+            Neighborhood::Custom(offsets) => offsets,
+        };
+        if offsets.len() > MAX_OFFSETS {
+            return Err(format!(
+                "a custom neighborhood can have at most {} offsets",
+                MAX_OFFSETS
+            ));
+        }
+        if offsets.contains(&(0, 0)) {
+            return Err(String::from(
+                "a custom neighborhood can't count the cell itself (0,0)",
+            ));
+        }
+        let far = |d: i32| d.unsigned_abs() > MAX_RADIUS;
+        match offsets.iter().find(|&&(dx, dy)| far(dx) || far(dy)) {
+            Some((dx, dy)) => Err(format!(
+                "the offset {},{} is more than {} cells away",
+                dx, dy, MAX_RADIUS
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // Returns every (dx, dy) offset in the neighborhood, never including (0, 0)
+    pub fn offsets(&self) -> Vec<(i32, i32)> {
+        let r = match self {
+            Neighborhood::Moore(r) | Neighborhood::VonNeumann(r) | Neighborhood::Cross(r) => {
+                *r as i32
+            }
+            Neighborhood::Custom(offsets) => {
+                return offsets
+                    .iter()
+                    .copied()
+                    .filter(|&offset| offset != (0, 0))
+                    .collect();
+            }
+        };
+        let mut offsets = vec![];
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let inside = match self {
+                    Neighborhood::VonNeumann(_) => dx.abs() + dy.abs() <= r,
+                    Neighborhood::Cross(_) => dx == 0 || dy == 0,
+                    _ => true,
+                };
+                if (dx, dy) != (0, 0) && inside {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        offsets
+    }
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    // Rows may be ragged, the missing cells are state 0
+    pub fn from_rows(rows: &[Vec<usize>]) -> Self {
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut grid = Grid::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &state) in row.iter().enumerate() {
+                grid.set(x, y, state);
+            }
+        }
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> usize {
+        self.cells[y * self.width + x] as usize
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, state: usize) {
+        self.cells[y * self.width + x] = state as Cell;
+    }
+
+    // Every cell, row after row
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [Cell] {
+        &mut self.cells
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    // The smallest (x, y, width, height) rectangle holding every non-zero cell
+    pub fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+        let rows = (0..self.height).filter(|&y| (0..self.width).any(|x| self.get(x, y) != 0));
+        let columns = (0..self.width).filter(|&x| (0..self.height).any(|y| self.get(x, y) != 0));
+        let (top, bottom) = (rows.clone().min()?, rows.max()?);
+        let (left, right) = (columns.clone().min()?, columns.max()?);
+        Some((left, top, right - left + 1, bottom - top + 1))
+    }
+
+    pub fn region(&self, x: usize, y: usize, width: usize, height: usize) -> Grid {
+        let mut region = Grid::new(width, height);
+        for dy in 0..height {
+            let row = (y + dy) * self.width + x;
+            region.cells[dy * width..(dy + 1) * width]
+                .copy_from_slice(&self.cells[row..row + width]);
+        }
+        region
+    }
+
+    // Copies cells onto the grid with their top left corner at (x, y), dropping
+    // whatever falls off the edges
+    pub fn paste(&mut self, cells: &Grid, x: i32, y: i32) {
+        for dy in 0..cells.height {
+            for dx in 0..cells.width {
+                let (x, y) = (x + dx as i32, y + dy as i32);
+                if y >= 0 && (y as usize) < self.height && x >= 0 && (x as usize) < self.width {
+                    self.set(x as usize, y as usize, cells.get(dx, dy));
+                }
+            }
+        }
+    }
+
+    // A copy of the grid at a new size, cropped or padded with state 0 around
+    // the anchor. Growing and then shrinking by the same amount gives the
+    // original grid back.
+    pub fn resized(&self, width: usize, height: usize, anchor: Anchor) -> Grid {
+        let (x, y) = match anchor {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Center => (
+                (width as i32 - self.width as i32) / 2,
+                (height as i32 - self.height as i32) / 2,
+            ),
+        };
+        let mut grid = Grid::new(width, height);
+        grid.paste(self, x, y);
+        grid
+    }
+
+    // The grid turned a quarter turn clockwise
+    pub fn rotated(&self) -> Grid {
+        let mut grid = Grid::new(self.height, self.width);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                grid.set(self.height - 1 - y, x, self.get(x, y));
+            }
+        }
+        grid
+    }
+
+    // The grid mirrored left to right
+    pub fn flipped_horizontally(&self) -> Grid {
+        let mut grid = self.clone();
+        for row in grid.cells.chunks_mut(self.width.max(1)) {
+            row.reverse();
+        }
+        grid
+    }
+
+    // The grid mirrored top to bottom
+    pub fn flipped_vertically(&self) -> Grid {
+        let mut grid = Grid::new(self.width, self.height);
+        for (y, row) in self.rows().enumerate() {
+            let y = self.height - 1 - y;
+            grid.cells[y * self.width..(y + 1) * self.width].copy_from_slice(row);
+        }
+        grid
+    }
+
+    // Sets every cell in the (x, y, width, height) rectangle to whatever state
+    // f gives it
+    pub fn fill(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        mut f: impl FnMut() -> usize,
+    ) {
+        for y in y..y + height {
+            for x in x..x + width {
+                self.set(x, y, f());
+            }
+        }
+    }
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize, state_types: Option<Vec<State>>) -> Self {
+        if let Some(state_types) = state_types {
+            Board {
+                grid: Grid::new(width, height),
+                state_types,
+                neighborhood: Neighborhood::Moore(1),
+                boundary_x: Boundary::Wrap,
+                boundary_y: Boundary::Wrap,
+                random: Random::new(0),
+                back: Grid::new(width, height),
+            }
+        } else {
+            // By default, the board uses regular game of life rules.
+            let state_0 = State::new(
+                0,                       // index
+                String::from("#000000"), // color
+                0,                       // fail state
+                vec![Rule::new(
+                    1,                                        // target state
+                    Condition::count(1, Predicate::Equal(3)), // 3 neighbors of state 1
+                )],
+            );
+
+            let state_1 = State::new(
+                1,
+                String::from("#FFFFFF"),
+                0,
+                vec![
+                    Rule::new(1, Condition::count(1, Predicate::Equal(2))),
+                    Rule::new(1, Condition::count(1, Predicate::Equal(3))),
+                ],
+            );
+            let states = vec![state_0, state_1];
+            Board {
+                grid: Grid::new(width, height),
+                state_types: states,
+                neighborhood: Neighborhood::Moore(1),
+                boundary_x: Boundary::Wrap,
+                boundary_y: Boundary::Wrap,
+                random: Random::new(0),
+                back: Grid::new(width, height),
+            }
+        }
+    }
+
+    // Swaps in a whole new set of states, sending cells and borders in states
+    // that no longer exist to state 0
+    pub fn set_state_types(&mut self, state_types: Vec<State>) {
+        let len = state_types.len();
+        for cell in self.grid.cells_mut() {
+            if *cell as usize >= len {
+                *cell = 0;
+            }
+        }
+        for edge in [&mut self.boundary_x, &mut self.boundary_y] {
+            if matches!(edge, Boundary::State(state) if *state >= len) {
+                *edge = Boundary::State(0);
+            }
+        }
+        self.state_types = state_types;
+    }
+
+    // How many cells are in each state
+    pub fn population(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.state_types.len()];
+        for &cell in self.grid.cells() {
+            counts[cell as usize] += 1;
+        }
+        counts
+    }
+
+    // Moves the board on one generation, using a faster kernel when the rules
+    // allow one
+    pub fn step(&mut self) {
+        match bitwise::life_masks(self) {
+            Some(masks) => self.step_into(|board, next| bitwise::step(board, masks, next)),
+            None => self.step_generic(),
+        }
+    }
+
+    // Starts the dice over, so the run from here can be repeated
+    pub fn reseed(&mut self, seed: u64) {
+        self.random = Random::new(seed);
+    }
+
+    // Whether every rule always fires when its condition holds
+    pub fn is_deterministic(&self) -> bool {
+        is_deterministic(&self.state_types)
+    }
+
+    // Moves the board on one generation by checking every cell against its
+    // rules. The fast kernels must always agree with this. Cells roll their
+    // dice in row order, so the same seed always gives the same generation.
+    pub fn step_generic(&mut self) {
+        let mut random = self.random;
+        self.step_into(|board, next| {
+            let (width, height) = (board.grid.width, board.grid.height);
+            let offsets = board.neighborhood.offsets();
+            let mut counts = vec![0; board.state_types.len()];
+            for y in 0..height {
+                for x in 0..width {
+                    board.count_neighbors(x as i32, y as i32, &offsets, &mut counts);
+                    let state = board.grid.get(x, y);
+                    next.cells[y * width + x] =
+                        transition(&board.state_types, state, &counts, &mut random) as Cell;
+                }
+            }
+        });
+        self.random = random;
+    }
+
+    // The state a cell in state moves to given its neighbor counts. Any dice
+    // are rolled on a copy of the board's generator, which is left as it was.
+    pub fn transition(&self, state: usize, counts: &[u32]) -> usize {
+        transition(&self.state_types, state, counts, &mut self.random.clone())
+    }
+
+    // Hands a kernel the back buffer to write the next generation into
+    fn step_into(&mut self, kernel: impl FnOnce(&Board, &mut Grid)) {
+        let (width, height) = (self.grid.width, self.grid.height);
+        let mut next = std::mem::take(&mut self.back);
+        if next.width != width || next.height != height {
+            next = Grid::new(width, height);
+        }
+        kernel(self, &mut next);
+        self.back = std::mem::replace(&mut self.grid, next);
+    }
+
+    // Looks up a cell that may lie off the board by applying the boundary conditions
+    pub fn cell_at(&self, x: i32, y: i32) -> usize {
+        let height = self.grid.height as i32;
+        let width = self.grid.width as i32;
+        let (mut x, mut y) = (x, y);
+        if !(0..width).contains(&x) {
+            match self.boundary_x {
+                Boundary::Wrap => {}
+                Boundary::Twist => {
+                    if x.div_euclid(width) % 2 != 0 {
+                        y = height - 1 - y;
+                    }
+                }
+                Boundary::Reflect => x = reflect(x, width),
+                Boundary::Dead => return 0,
+                Boundary::State(state) => return state,
+            }
+            x = x.rem_euclid(width);
+        }
+        if !(0..height).contains(&y) {
+            match self.boundary_y {
+                Boundary::Wrap => {}
+                Boundary::Twist => {
+                    if y.div_euclid(height) % 2 != 0 {
+                        x = width - 1 - x;
+                    }
+                }
+                Boundary::Reflect => y = reflect(y, height),
+                Boundary::Dead => return 0,
+                Boundary::State(state) => return state,
+            }
+            y = y.rem_euclid(height);
+        }
+        self.grid.get(x as usize, y as usize)
+    }
+
+    fn count_neighbors(&self, x: i32, y: i32, offsets: &[(i32, i32)], counts: &mut [u32]) {
+        counts.fill(0);
+        for (dx, dy) in offsets {
+            counts[self.state_types[self.cell_at(x + dx, y + dy)].index] += 1;
+        }
+    }
+}
+
+// The first rule of a state whose condition holds and whose dice come up picks
+// the next state, falling back to the state's fail state if its dice come up
+pub fn transition(
+    state_types: &[State],
+    state: usize,
+    counts: &[u32],
+    random: &mut Random,
+) -> usize {
+    let current = &state_types[state];
+    current
+        .rules
+        .iter()
+        .find(|rule| rule.condition.test(counts) && random.chance(rule.probability))
+        .map_or_else(
+            || match random.chance(current.fail_probability) {
+                true => current.fail_state,
+                false => state,
+            },
+            |rule| state_types[rule.target_state].index,
+        )
+}
+
+// Whether a cell in state could move to another state with these neighbor
+// counts, however the dice come up
+pub fn can_change(state_types: &[State], state: usize, counts: &[u32]) -> bool {
+    let current = &state_types[state];
+    for rule in &current.rules {
+        if !rule.condition.test(counts) || rule.probability <= 0.0 {
+            continue;
+        }
+        if state_types[rule.target_state].index != state {
+            return true;
+        }
+        if rule.probability >= 1.0 {
+            return false;
+        }
+    }
+    current.fail_state != state && current.fail_probability > 0.0
+}
+
+pub fn is_deterministic(state_types: &[State]) -> bool {
+    state_types.iter().all(|state| {
+        state.fail_probability >= 1.0 && state.rules.iter().all(|rule| rule.probability >= 1.0)
+    })
+}
+
+// Folds a coordinate back onto 0..len as if the board were mirrored at each edge
+fn reflect(n: i32, len: i32) -> i32 {
+    let n = n.rem_euclid(2 * len);
+    if n >= len {
+        2 * len - 1 - n
+    } else {
+        n
+    }
+}
@@ -0,0 +1,229 @@
+use crate::life::Grid;
+use crate::rle;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Category {
+    StillLife,
+    Oscillator,
+    Spaceship,
+    Gun,
+}
+
+pub const CATEGORIES: [Category; 4] = [
+    Category::StillLife,
+    Category::Oscillator,
+    Category::Spaceship,
+    Category::Gun,
+];
+
+impl Category {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::StillLife => "Still Lifes",
+            Category::Oscillator => "Oscillators",
+            Category::Spaceship => "Spaceships",
+            Category::Gun => "Guns",
+        }
+    }
+}
+
+// A well known pattern and the rulestrings it works under, or for rules with
+// no rulestring the names of the presets it works under. Still lifes,
+// oscillators and spaceships come back to the same shape every period
+// generations, and guns fire something every period generations.
+pub struct Entry {
+    pub name: &'static str,
+    pub category: Category,
+    pub rules: &'static [&'static str],
+    pub period: usize,
+    pub rle: &'static str,
+}
+
+impl Entry {
+    pub fn cells(&self) -> Grid {
+        rle::parse(self.rle)
+            .expect("library patterns to parse")
+            .cells
+    }
+}
+
+const LIFE: &[&str] = &["B3/S23"];
+const LIFE_AND_HIGHLIFE: &[&str] = &["B3/S23", "B36/S23"];
+const BRIANS_BRAIN: &[&str] = &["B2/S/C3"];
+const STAR_WARS: &[&str] = &["B2/S345/C4"];
+const BOSCOS_RULE: &[&str] = &["R5,C2,M0,S33..57,B34..45,NM"];
+const SEEDS: &[&str] = &["B2/S"];
+const WIREWORLD: &[&str] = &["Wireworld"];
+const ROCK_PAPER_SCISSORS: &[&str] = &["Rock Paper Scissors"];
+// Cyclic and Forest Fire have none: in Cyclic anything small on an empty
+// board dies out, and Forest Fire is left to chance
+
+pub const LIBRARY: &[Entry] = &[
+    Entry {
+        name: "Block",
+        category: Category::StillLife,
+        rules: &["B3/S23", "B36/S23", "B3678/S34678"],
+        period: 1,
+        rle: "x = 2, y = 2\n2o$2o!",
+    },
+    Entry {
+        name: "Beehive",
+        category: Category::StillLife,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 1,
+        rle: "x = 4, y = 3\nb2o$o2bo$b2o!",
+    },
+    Entry {
+        name: "Loaf",
+        category: Category::StillLife,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 1,
+        rle: "x = 4, y = 4\nb2o$o2bo$bobo$2bo!",
+    },
+    Entry {
+        name: "Boat",
+        category: Category::StillLife,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 1,
+        rle: "x = 3, y = 3\n2o$obo$bo!",
+    },
+    Entry {
+        name: "Tub",
+        category: Category::StillLife,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 1,
+        rle: "x = 3, y = 3\nbo$obo$bo!",
+    },
+    Entry {
+        name: "Plus",
+        category: Category::StillLife,
+        rules: STAR_WARS,
+        period: 1,
+        rle: "x = 3, y = 3\nbo$3o$bo!",
+    },
+    Entry {
+        name: "Big Block",
+        category: Category::StillLife,
+        rules: BOSCOS_RULE,
+        period: 1,
+        rle: "x = 6, y = 6\n6o$6o$6o$6o$6o$6o!",
+    },
+    Entry {
+        name: "Paper Block",
+        category: Category::StillLife,
+        rules: ROCK_PAPER_SCISSORS,
+        period: 1,
+        rle: "x = 2, y = 2\n2A$2A!",
+    },
+    Entry {
+        name: "Flicker",
+        category: Category::Oscillator,
+        rules: SEEDS,
+        period: 2,
+        rle: "x = 3, y = 3\n2bo$o$bo!",
+    },
+    // An electron going round a loop with its corners cut
+    Entry {
+        name: "Electron Loop",
+        category: Category::Oscillator,
+        rules: WIREWORLD,
+        period: 18,
+        rle: "x = 8, y = 5\n.BA4C$C6.C$C6.C$C6.C$.6C!",
+    },
+    Entry {
+        name: "Blinker",
+        category: Category::Oscillator,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 2,
+        rle: "x = 3, y = 1\n3o!",
+    },
+    Entry {
+        name: "Toad",
+        category: Category::Oscillator,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 2,
+        rle: "x = 4, y = 2\nb3o$3o!",
+    },
+    Entry {
+        name: "Beacon",
+        category: Category::Oscillator,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 2,
+        rle: "x = 4, y = 4\n2o$2o$2b2o$2b2o!",
+    },
+    Entry {
+        name: "Pulsar",
+        category: Category::Oscillator,
+        rules: LIFE,
+        period: 3,
+        rle: "x = 13, y = 13\n2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$\
+              o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    },
+    Entry {
+        name: "Pentadecathlon",
+        category: Category::Oscillator,
+        rules: LIFE,
+        period: 15,
+        rle: "x = 10, y = 3\n2bo4bo$2ob4ob2o$2bo4bo!",
+    },
+    Entry {
+        name: "Glider",
+        category: Category::Spaceship,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 4,
+        rle: "x = 3, y = 3\nbo$2bo$3o!",
+    },
+    Entry {
+        name: "Lightweight Spaceship",
+        category: Category::Spaceship,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 4,
+        rle: "x = 5, y = 4\nbo2bo$o$o3bo$4o!",
+    },
+    Entry {
+        name: "Middleweight Spaceship",
+        category: Category::Spaceship,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 4,
+        rle: "x = 6, y = 5\n3bo$bo3bo$o$o4bo$5o!",
+    },
+    Entry {
+        name: "Heavyweight Spaceship",
+        category: Category::Spaceship,
+        rules: LIFE_AND_HIGHLIFE,
+        period: 4,
+        rle: "x = 7, y = 5\n3b2o$bo4bo$o$o5bo$6o!",
+    },
+    // Two firing cells with two refractory ones behind them, moving a cell
+    // every generation
+    Entry {
+        name: "Brian's Brain Spaceship",
+        category: Category::Spaceship,
+        rules: BRIANS_BRAIN,
+        period: 1,
+        rle: "x = 2, y = 2\n2A$2B!",
+    },
+    Entry {
+        name: "Gosper Glider Gun",
+        category: Category::Gun,
+        rules: LIFE,
+        period: 30,
+        rle: "x = 36, y = 9\n24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$\
+              2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!",
+    },
+    Entry {
+        name: "Simkin Glider Gun",
+        category: Category::Gun,
+        rules: LIFE,
+        period: 120,
+        rle: "x = 33, y = 21\n2o5b2o$2o5b2o2$4b2o$4b2o5$22b2ob2o$21bo5bo$21bo6bo2b2o$\
+              21b3o3bo3b2o$26bo4$20b2o$20bo$21b3o$23bo!",
+    },
+];
+
+// The patterns that work under a rulestring, as written by rulestring::format
+pub fn for_rule(rule: &str) -> impl Iterator<Item = &'static Entry> + '_ {
+    LIBRARY
+        .iter()
+        .filter(move |entry| entry.rules.contains(&rule))
+}
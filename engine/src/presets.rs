@@ -0,0 +1,303 @@
+use crate::brush;
+use crate::life::*;
+use crate::patterns::LIBRARY;
+use crate::random::Random;
+use crate::rle;
+use crate::rulestring;
+
+// A well known automaton, with colors that suit it and a demo to start from
+pub struct Preset {
+    pub name: &'static str,
+    rules: fn() -> (Vec<State>, Neighborhood),
+    demo: fn(usize, usize) -> Grid,
+}
+
+impl Preset {
+    // A width x height board running the preset with its demo on it
+    pub fn board(&self, width: usize, height: usize) -> Board {
+        let (states, neighborhood) = (self.rules)();
+        let mut board = Board::new(width, height, Some(states));
+        board.neighborhood = neighborhood;
+        board.grid = (self.demo)(width, height);
+        board
+    }
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "Game of Life",
+        rules: life,
+        demo: glider_gun,
+    },
+    Preset {
+        name: "HighLife",
+        rules: highlife,
+        demo: replicator,
+    },
+    Preset {
+        name: "Seeds",
+        rules: seeds,
+        demo: small_soup,
+    },
+    Preset {
+        name: "Day & Night",
+        rules: day_and_night,
+        demo: half_soup,
+    },
+    Preset {
+        name: "Brian's Brain",
+        rules: brians_brain,
+        demo: sparse_soup,
+    },
+    Preset {
+        name: "Star Wars",
+        rules: star_wars,
+        demo: sparse_soup,
+    },
+    Preset {
+        name: "Wireworld",
+        rules: wireworld,
+        demo: clock,
+    },
+    Preset {
+        name: "Cyclic",
+        rules: cyclic,
+        demo: cyclic_soup,
+    },
+    Preset {
+        name: "Rock Paper Scissors",
+        rules: rock_paper_scissors,
+        demo: rock_paper_scissors_soup,
+    },
+    Preset {
+        name: "Forest Fire",
+        rules: forest_fire,
+        demo: forest,
+    },
+];
+
+// The preset the board runs, whatever its colors, which names rules that have
+// no rulestring
+pub fn matching(board: &Board) -> Option<&'static Preset> {
+    let uncolored = |states: &[State]| {
+        let mut states = states.to_vec();
+        for state in &mut states {
+            state.color.clear();
+        }
+        states
+    };
+    PRESETS.iter().find(|preset| {
+        let (states, neighborhood) = (preset.rules)();
+        neighborhood == board.neighborhood && uncolored(&states) == uncolored(&board.state_types)
+    })
+}
+
+// A rulestring's states with their colors swapped for the given ones
+fn life_like(rule: &str, colors: &[&str]) -> (Vec<State>, Neighborhood) {
+    let (mut states, neighborhood) = rulestring::parse(rule).expect("preset rules to parse");
+    for (state, color) in states.iter_mut().zip(colors) {
+        state.color = color.to_string();
+    }
+    (states, neighborhood)
+}
+
+fn life() -> (Vec<State>, Neighborhood) {
+    life_like("B3/S23", &["#000000", "#FFFFFF"])
+}
+
+fn highlife() -> (Vec<State>, Neighborhood) {
+    life_like("B36/S23", &["#000000", "#FFD23F"])
+}
+
+fn seeds() -> (Vec<State>, Neighborhood) {
+    life_like("B2/S", &["#000000", "#7FDBFF"])
+}
+
+fn day_and_night() -> (Vec<State>, Neighborhood) {
+    life_like("B3678/S34678", &["#0B1D51", "#FFE066"])
+}
+
+fn brians_brain() -> (Vec<State>, Neighborhood) {
+    life_like("B2/S/C3", &["#000000", "#FFFFFF", "#3F7FFF"])
+}
+
+fn star_wars() -> (Vec<State>, Neighborhood) {
+    life_like("B2/S345/C4", &["#000000", "#FFFFFF", "#FF4136", "#85144B"])
+}
+
+// Electrons run along conductors: a head always becomes a tail, a tail always
+// becomes conductor, and conductor becomes a head next to one or two heads
+fn wireworld() -> (Vec<State>, Neighborhood) {
+    let states = vec![
+        State::new(0, String::from("#000000"), 0, vec![]),
+        State::new(1, String::from("#3F7FFF"), 2, vec![]),
+        State::new(2, String::from("#FF3F1F"), 3, vec![]),
+        State::new(
+            3,
+            String::from("#FFBF00"),
+            3,
+            vec![Rule::new(1, Condition::count(1, Predicate::InRange(1, 2)))],
+        ),
+    ];
+    (states, Neighborhood::Moore(1))
+}
+
+// Each state is eaten by the next one round a cycle once enough of it is
+// next door, which sorts a soup into spirals
+fn cycle(colors: &[&str], threshold: u32) -> Vec<State> {
+    let n = colors.len();
+    colors
+        .iter()
+        .enumerate()
+        .map(|(index, color)| {
+            let next = (index + 1) % n;
+            let rules = vec![Rule::new(
+                next,
+                Condition::count(next, Predicate::GreaterEqual(threshold)),
+            )];
+            State::new(index, color.to_string(), index, rules)
+        })
+        .collect()
+}
+
+fn cyclic() -> (Vec<State>, Neighborhood) {
+    let colors = (0..CYCLIC_STATES)
+        .map(|i| hue(i as f64 / CYCLIC_STATES as f64))
+        .collect::<Vec<_>>();
+    let colors = colors.iter().map(String::as_str).collect::<Vec<_>>();
+    (cycle(&colors, 1), Neighborhood::VonNeumann(1))
+}
+
+fn rock_paper_scissors() -> (Vec<State>, Neighborhood) {
+    let colors = ["#FF4136", "#2ECC40", "#0074D9"];
+    (cycle(&colors, 3), Neighborhood::Moore(1))
+}
+
+// Fire spreads to any tree next to it and burns out in a generation, while
+// trees slowly grow back on empty ground and are now and then struck by
+// lightning
+fn forest_fire() -> (Vec<State>, Neighborhood) {
+    let states = vec![
+        State::new(0, String::from("#3D2B1F"), 1, vec![]).with_fail_probability(0.01),
+        State::new(
+            1,
+            String::from("#2ECC40"),
+            2,
+            vec![Rule::new(
+                2,
+                Condition::count(2, Predicate::GreaterEqual(1)),
+            )],
+        )
+        .with_fail_probability(0.00002),
+        State::new(2, String::from("#FF851B"), 0, vec![]),
+    ];
+    (states, Neighborhood::Moore(1))
+}
+
+const CYCLIC_STATES: usize = 14;
+const SEED: u64 = 0x5EED;
+
+// A fully saturated color a fraction of the way round the color wheel
+fn hue(fraction: f64) -> String {
+    let h = fraction * 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    let byte = |c: f64| (c * 255.0).round() as u8;
+    format!("#{:02X}{:02X}{:02X}", byte(r), byte(g), byte(b))
+}
+
+fn centered(width: usize, height: usize, cells: &Grid) -> Grid {
+    let mut grid = Grid::new(width, height);
+    grid.paste(
+        cells,
+        (width as i32 - cells.width() as i32) / 2,
+        (height as i32 - cells.height() as i32) / 2,
+    );
+    grid
+}
+
+// Random cells from a fixed seed, so a preset's demo is the same every time
+fn soup(width: usize, height: usize, pick: impl Fn(u64) -> usize) -> Grid {
+    let mut grid = Grid::new(width, height);
+    let mut random = Random::new(SEED);
+    for cell in grid.cells_mut() {
+        *cell = pick(random.next_u64() >> 32) as Cell;
+    }
+    grid
+}
+
+fn glider_gun(width: usize, height: usize) -> Grid {
+    let gun = LIBRARY
+        .iter()
+        .find(|entry| entry.name == "Gosper Glider Gun");
+    centered(
+        width,
+        height,
+        &gun.map(|gun| gun.cells()).unwrap_or_default(),
+    )
+}
+
+fn replicator(width: usize, height: usize) -> Grid {
+    let cells = rle::parse("x = 5, y = 5\n2b3o$bo2bo$o3bo$o2bo$3o!").unwrap();
+    centered(width, height, &cells.cells)
+}
+
+fn small_soup(width: usize, height: usize) -> Grid {
+    centered(width, height, &soup(6, 6, |r| (r % 3 == 0) as usize))
+}
+
+fn half_soup(width: usize, height: usize) -> Grid {
+    soup(width, height, |r| (r % 2) as usize)
+}
+
+fn sparse_soup(width: usize, height: usize) -> Grid {
+    soup(width, height, |r| (r % 10 < 3) as usize)
+}
+
+fn cyclic_soup(width: usize, height: usize) -> Grid {
+    soup(width, height, |r| r as usize % CYCLIC_STATES)
+}
+
+fn rock_paper_scissors_soup(width: usize, height: usize) -> Grid {
+    soup(width, height, |r| r as usize % 3)
+}
+
+// A thick forest with a line of fire down the left edge
+fn forest(width: usize, height: usize) -> Grid {
+    let mut grid = soup(width, height, |r| (r % 20 < 13) as usize);
+    let fire = brush::line((0, 0), (0, height as i64 - 1));
+    brush::paint(&mut grid, &fire, 1, 2);
+    grid
+}
+
+// A loop with an electron going round it, sending a pulse down a wire to the
+// right every lap
+fn clock(width: usize, height: usize) -> Grid {
+    let mut grid = Grid::new(width, height);
+    if width < 14 || height < 5 {
+        return grid;
+    }
+    // Cut corners, so electrons can only go round it one way
+    let (x, y) = (2, (height / 2 - 2) as i64);
+    brush::paint(
+        &mut grid,
+        &brush::rectangle((x, y), (x + 7, y + 4), false),
+        1,
+        3,
+    );
+    for (cx, cy) in [(x, y), (x + 7, y), (x, y + 4), (x + 7, y + 4)] {
+        grid.set(cx as usize, cy as usize, 0);
+    }
+    let wire = brush::line((x + 8, y + 2), (width as i64 - 3, y + 2));
+    brush::paint(&mut grid, &wire, 1, 3);
+    grid.set(x as usize + 1, y as usize, 2);
+    grid.set(x as usize + 2, y as usize, 1);
+    grid
+}
@@ -0,0 +1,211 @@
+use crate::life::*;
+use crate::rle;
+use serde::{Deserialize, Serialize};
+
+// Bump when the format changes, and teach from_json to read the older versions.
+// Version 2 added the probabilities and the seed, which version 1 projects
+// take the defaults for.
+pub const VERSION: u32 = 2;
+pub const MAX_SIZE: usize = 1024;
+pub const MAX_STATES: usize = 256;
+pub const DELAY_RANGE: (u64, u64) = (10, 5000);
+
+// Everything needed to rebuild an automaton, saved as JSON. The grid is kept
+// as an RLE pattern without a rule so large boards stay small.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub version: u32,
+    pub width: usize,
+    pub height: usize,
+    pub delay: u64,
+    pub neighborhood: Neighborhood,
+    pub boundary_x: Boundary,
+    pub boundary_y: Boundary,
+    pub states: Vec<State>,
+    pub grid: String,
+    // Where the dice had got to, so rules left to chance carry on as they would have
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl Project {
+    pub fn new(board: &Board, delay: u64) -> Self {
+        Project {
+            version: VERSION,
+            width: board.grid.width(),
+            height: board.grid.height(),
+            delay,
+            neighborhood: board.neighborhood.clone(),
+            boundary_x: board.boundary_x.clone(),
+            boundary_y: board.boundary_y.clone(),
+            states: board.state_types.clone(),
+            grid: rle::write(&board.grid, None),
+            seed: board.random.state(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    // The same without the indentation, for storing rather than reading
+    pub fn to_compact_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Project, String> {
+        let mut project: Project =
+            serde_json::from_str(json).map_err(|e| format!("not a valid project: {}", e))?;
+        project.validate()?;
+        project.version = VERSION;
+        Ok(project)
+    }
+
+    // A compact, URL safe form of the project for sharing in links
+    pub fn to_link(&self) -> String {
+        encode_base64(self.to_compact_json().as_bytes())
+    }
+
+    pub fn from_link(link: &str) -> Result<Project, String> {
+        let damaged = || String::from("the link is damaged or incomplete");
+        let json = decode_base64(link).ok_or_else(damaged)?;
+        Project::from_json(&String::from_utf8(json).map_err(|_| damaged())?)
+    }
+
+    // Fails if the project can't be loaded, e.g. a rule points at a missing state
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version == 0 || self.version > VERSION {
+            return Err(format!(
+                "project version {} isn't supported, the newest is {}",
+                self.version, VERSION
+            ));
+        }
+        for (name, size) in [("width", self.width), ("height", self.height)] {
+            if size == 0 || size > MAX_SIZE {
+                return Err(format!("the {} must be from 1 to {}", name, MAX_SIZE));
+            }
+        }
+        let (min, max) = DELAY_RANGE;
+        if self.delay < min || self.delay > max {
+            return Err(format!("the delay must be from {} to {} ms", min, max));
+        }
+
+        let len = self.states.len();
+        if len == 0 || len > MAX_STATES {
+            return Err(format!("there must be from 1 to {} states", MAX_STATES));
+        }
+        let probability = |p: f64, what: String| match (0.0..=1.0).contains(&p) {
+            true => Ok(()),
+            false => Err(format!("{} has probability {}, expected 0 to 1", what, p)),
+        };
+        let exists = |state: usize, what: String| match state < len {
+            true => Ok(()),
+            false => Err(format!(
+                "{} is state {}, but there are only {} states",
+                what, state, len
+            )),
+        };
+        for (i, state) in self.states.iter().enumerate() {
+            if state.index != i {
+                return Err(format!("state {} has index {}", i, state.index));
+            }
+            if !is_color(&state.color) {
+                return Err(format!(
+                    "state {} has color {}, expected one like #00FF00",
+                    i, state.color
+                ));
+            }
+            exists(state.fail_state, format!("the fail state of state {}", i))?;
+            probability(
+                state.fail_probability,
+                format!("the fail state of state {}", i),
+            )?;
+            for (j, rule) in state.rules.iter().enumerate() {
+                let what = format!("state {} rule {}", i, j);
+                exists(rule.target_state, format!("the target of {}", what))?;
+                probability(rule.probability, what.clone())?;
+                for count_state in rule.condition.count_states() {
+                    exists(count_state, format!("a count in {}", what))?;
+                }
+            }
+        }
+        self.neighborhood.validate()?;
+        for (name, edge) in [
+            ("horizontal", &self.boundary_x),
+            ("vertical", &self.boundary_y),
+        ] {
+            if let Boundary::State(state) = edge {
+                exists(*state, format!("the {} border", name))?;
+            }
+        }
+
+        let grid = self.cells()?;
+        if grid.width != self.width || grid.height != self.height {
+            return Err(format!(
+                "the grid is {}x{} but the project says {}x{}",
+                grid.width, grid.height, self.width, self.height
+            ));
+        }
+        if let Some(&state) = grid.cells.cells().iter().find(|&&c| c as usize >= len) {
+            exists(state as usize, String::from("a cell in the grid"))?;
+        }
+        Ok(())
+    }
+
+    // Only call on a validated project
+    pub fn into_board(self) -> Board {
+        let cells = self.cells().unwrap().cells;
+        let mut board = Board::new(self.width, self.height, Some(self.states));
+        board.grid = cells;
+        board.neighborhood = self.neighborhood;
+        board.boundary_x = self.boundary_x;
+        board.boundary_y = self.boundary_y;
+        board.reseed(self.seed);
+        board
+    }
+
+    // Held to the project's own size, so the grid can't ask for more
+    fn cells(&self) -> Result<rle::Pattern, String> {
+        rle::parse_within(&self.grid, self.width, self.height)
+            .map_err(|e| format!("couldn't read the grid: {}", e))
+    }
+}
+
+fn is_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// The URL safe base64 alphabet, written without padding
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, &b)| bits | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            text.push(BASE64[(bits >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    text
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    for chunk in text.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut bits = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64.iter().position(|b| b == c)? as u32;
+            bits |= value << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
@@ -0,0 +1,43 @@
+// A SplitMix64 generator. It gives the same numbers from the same seed on
+// every platform, and its state is itself a seed that carries on the sequence,
+// so a board saved mid-run picks up exactly where it left off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    // The seed that carries on from here
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // True with the given probability. Certain and impossible outcomes don't
+    // draw a number, so deterministic rules leave the sequence alone.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        if probability >= 1.0 {
+            true
+        } else if probability <= 0.0 {
+            false
+        } else {
+            self.next_f64() < probability
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// Reads and writes patterns in the run length encoded format used by Golly and
+// the LifeWiki, e.g.
+//
+//   #N Glider
+//   x = 3, y = 3, rule = B3/S23
+//   bob$2bo$3o!
+//
+// Two state patterns use b for state 0 and o for state 1. Patterns with more
+// states use . for state 0, A to X for states 1 to 24, and pA to yO for the
+// states above that.
+
+use crate::life::{Cell, Grid};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Grid,
+    pub rule: Option<String>,
+}
+
+const LINE_LENGTH: usize = 70;
+// The widest or tallest pattern read, so that a header or a run can't ask for
+// more memory than there is
+pub const MAX_SIZE: usize = 4096;
+
+pub fn parse(text: &str) -> Result<Pattern, String> {
+    parse_within(text, MAX_SIZE, MAX_SIZE)
+}
+
+// Reads a pattern, failing before anything is allocated if it's wider or
+// taller than the given bounds
+pub fn parse_within(text: &str, max_width: usize, max_height: usize) -> Result<Pattern, String> {
+    let mut lines = text
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let header = lines.next().ok_or("the pattern is empty")?;
+
+    // The rule comes last and runs to the end of the line, since rules such
+    // as Larger than Life ones have commas of their own
+    let (sizes, rule) = match header.find("rule") {
+        Some(i) => (&header[..i], Some(&header[i..])),
+        None => (header, None),
+    };
+    let bad_header = || format!("expected a header like x = 3, y = 3, got {}", header);
+    let rule = match rule {
+        Some(field) => Some(
+            field
+                .split_once('=')
+                .ok_or_else(bad_header)?
+                .1
+                .trim()
+                .to_string(),
+        ),
+        None => None,
+    };
+    let (mut width, mut height) = (None, None);
+    for field in sizes.split(',').filter(|field| !field.trim().is_empty()) {
+        let (key, value) = field.split_once('=').ok_or_else(bad_header)?;
+        let value = value.trim();
+        match key.trim() {
+            "x" => width = value.parse::<usize>().ok(),
+            "y" => height = value.parse::<usize>().ok(),
+            _ => {}
+        }
+    }
+    let width = width.ok_or("the header is missing a width (x)")?;
+    let height = height.ok_or("the header is missing a height (y)")?;
+    let too_big = || {
+        Err(format!(
+            "the pattern is bigger than {}x{}",
+            max_width, max_height
+        ))
+    };
+    if width > max_width || height > max_height {
+        return too_big();
+    }
+
+    let mut cells = vec![vec![]];
+    let mut run = String::new();
+    let mut prefix = None;
+    'body: for line in lines {
+        for c in line.chars() {
+            let state = match c {
+                '0'..='9' => {
+                    run.push(c);
+                    continue;
+                }
+                'p'..='y' if prefix.is_none() => {
+                    prefix = Some(c);
+                    continue;
+                }
+                '!' => break 'body,
+                '$' => None,
+                'b' | '.' => Some(0),
+                'o' => Some(1),
+                'A'..='X' => {
+                    let letter = c as usize - 'A' as usize + 1;
+                    let page = prefix.take().map_or(0, |p| p as usize - 'p' as usize + 1);
+                    Some(page * 24 + letter)
+                }
+                c if c.is_whitespace() => continue,
+                c => return Err(format!("unexpected {} in the pattern", c)),
+            };
+            if let Some(p) = prefix {
+                return Err(format!("{} must be followed by a letter from A to X", p));
+            }
+            let count = if run.is_empty() {
+                1
+            } else {
+                run.parse::<usize>().map_err(|e| e.to_string())?
+            };
+            run.clear();
+            match state {
+                Some(state) => {
+                    let row = cells.last_mut().unwrap();
+                    if count > max_width.saturating_sub(row.len()) {
+                        return too_big();
+                    }
+                    row.extend(vec![state; count]);
+                }
+                None => {
+                    if count > max_height.saturating_sub(cells.len()) {
+                        return too_big();
+                    }
+                    cells.extend(vec![vec![]; count]);
+                }
+            }
+        }
+    }
+    if !run.is_empty() || prefix.is_some() {
+        return Err(String::from("the pattern ends partway through a run"));
+    }
+
+    // Rows are only written up to their last non-zero cell, so pad them out
+    let width = width.max(cells.iter().map(|row| row.len()).max().unwrap_or(0));
+    let height = height.max(cells.len());
+    let mut grid = Grid::new(width, height);
+    grid.paste(&Grid::from_rows(&cells), 0, 0);
+    Ok(Pattern {
+        width,
+        height,
+        cells: grid,
+        rule,
+    })
+}
+
+pub fn write(cells: &Grid, rule: Option<&str>) -> String {
+    let mut header = format!("x = {}, y = {}", cells.width(), cells.height());
+    if let Some(rule) = rule {
+        header += &format!(", rule = {}", rule);
+    }
+
+    let two_state = cells.cells().iter().all(|&state| state <= 1);
+    let tag = |state: Cell| match state {
+        0 if two_state => String::from("b"),
+        1 if two_state => String::from("o"),
+        0 => String::from("."),
+        _ => {
+            let (page, letter) = ((state - 1) / 24, (state - 1) % 24);
+            let letter = (b'A' + letter as u8) as char;
+            match page {
+                0 => letter.to_string(),
+                _ => format!("{}{}", (b'p' + page as u8 - 1) as char, letter),
+            }
+        }
+    };
+    let runs = |count: usize, tag: &str| match count {
+        1 => tag.to_string(),
+        _ => format!("{}{}", count, tag),
+    };
+
+    let mut tokens = vec![];
+    let mut blank_rows = 0;
+    for (y, row) in cells.rows().enumerate() {
+        // Trailing state 0 cells are implied by the end of the row
+        let end = row
+            .iter()
+            .rposition(|&state| state != 0)
+            .map_or(0, |i| i + 1);
+        if y > 0 {
+            blank_rows += 1;
+        }
+        if end == 0 {
+            continue;
+        }
+        if blank_rows > 0 {
+            tokens.push(runs(blank_rows, "$"));
+            blank_rows = 0;
+        }
+        let mut x = 0;
+        while x < end {
+            let count = row[x..end].iter().take_while(|&&s| s == row[x]).count();
+            tokens.push(runs(count, &tag(row[x])));
+            x += count;
+        }
+    }
+    tokens.push(String::from("!"));
+
+    let mut lines = vec![header, String::new()];
+    for token in tokens {
+        let line = lines.last_mut().unwrap();
+        if line.len() + token.len() > LINE_LENGTH {
+            lines.push(token);
+        } else {
+            line.push_str(&token);
+        }
+    }
+    lines.join("\n") + "\n"
+}
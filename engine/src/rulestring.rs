@@ -0,0 +1,327 @@
+use crate::life::*;
+use crate::project::MAX_STATES;
+
+// Converts between rulestrings and state types. Understood notations are
+// Life-like B/S ("B3/S23", or Golly's digit-only S/B "23/3"), Generations
+// B/S/C ("B2/S/C3"), a trailing V for the von Neumann neighborhood, and
+// Larger than Life ("R5,C2,M1,S34..58,B34..45,NM"), whose counts may also be
+// HROT style lists of counts and ranges ("S2-3,5").
+//
+// State 0 is dead and state 1 is alive. In Generations rules a cell that fails
+// to survive goes through the dying states 2, 3, ... back to 0, and only
+// state 1 neighbors are counted.
+pub fn parse(rule: &str) -> Result<(Vec<State>, Neighborhood), String> {
+    let rule = rule.trim().to_uppercase();
+    if rule.starts_with('R') {
+        parse_larger_than_life(&rule)
+    } else {
+        parse_life_like(&rule)
+    }
+}
+
+// Fails for state types that don't behave like one of the notations above
+pub fn format(states: &[State], neighborhood: &Neighborhood) -> Result<String, String> {
+    let (birth, survival) = transitions(states, neighborhood)?;
+    let generations = states.len();
+    match neighborhood {
+        Neighborhood::Moore(1) | Neighborhood::VonNeumann(1) => {
+            let digits = |set: &[u32]| set.iter().map(|n| n.to_string()).collect::<String>();
+            let mut rule = format!("B{}/S{}", digits(&birth), digits(&survival));
+            if generations > 2 {
+                rule += &format!("/C{}", generations);
+            }
+            if let Neighborhood::VonNeumann(_) = neighborhood {
+                rule.push('V');
+            }
+            Ok(rule)
+        }
+        Neighborhood::Moore(r) | Neighborhood::VonNeumann(r) | Neighborhood::Cross(r) => {
+            let range = |set: &[u32], name: &str| match (set.first(), set.last()) {
+                (Some(min), Some(max)) if (max - min) as usize + 1 == set.len() => {
+                    Ok(format!("{}..{}", min, max))
+                }
+                (Some(_), _) => Ok(runs(set)),
+                _ => Err(format!("Larger than Life needs some {} counts", name)),
+            };
+            let shape = match neighborhood {
+                Neighborhood::VonNeumann(_) => 'N',
+                Neighborhood::Cross(_) => '+',
+                _ => 'M',
+            };
+            Ok(format!(
+                "R{},C{},M0,S{},B{},N{}",
+                r,
+                generations,
+                range(&survival, "survival")?,
+                range(&birth, "birth")?,
+                shape
+            ))
+        }
+        Neighborhood::Custom(_) => Err(String::from("custom neighborhoods have no rulestring")),
+    }
+}
+
+fn parse_life_like(rule: &str) -> Result<(Vec<State>, Neighborhood), String> {
+    let (rule, neighborhood) = match rule.strip_suffix('V') {
+        Some(rule) => (rule, Neighborhood::VonNeumann(1)),
+        None => (rule, Neighborhood::Moore(1)),
+    };
+    let cells = neighborhood.offsets().len() as u32;
+    let fields = rule.split('/').collect::<Vec<_>>();
+    if fields.len() < 2 || fields.len() > 3 {
+        return Err(format!("expected a rule like B3/S23, got {}", rule));
+    }
+
+    let (mut birth, mut survival, mut generations) = (None, None, 2);
+    if fields
+        .iter()
+        .any(|f| f.starts_with('B') || f.starts_with('S'))
+    {
+        for field in fields {
+            match field.chars().next() {
+                Some('B') => birth = Some(counts(&field[1..], cells)?),
+                Some('S') => survival = Some(counts(&field[1..], cells)?),
+                Some('C') => generations = number(&field[1..])?,
+                _ => generations = number(field)?,
+            }
+        }
+    } else {
+        if fields.iter().all(|f| f.is_empty()) {
+            return Err(String::from(
+                "expected a rule like B3/S23, got an empty one",
+            ));
+        }
+        survival = Some(counts(fields[0], cells)?);
+        birth = Some(counts(fields[1], cells)?);
+        if let Some(field) = fields.get(2) {
+            generations = number(field)?;
+        }
+    }
+    check_generations(generations)?;
+
+    let birth = birth.ok_or("missing birth (B) counts")?;
+    let survival = survival.ok_or("missing survival (S) counts")?;
+    let set = |counts: Vec<u32>| (!counts.is_empty()).then_some(Predicate::InSet(counts));
+    Ok((
+        generation_states(set(birth), set(survival), generations),
+        neighborhood,
+    ))
+}
+
+fn parse_larger_than_life(rule: &str) -> Result<(Vec<State>, Neighborhood), String> {
+    let (mut radius, mut generations, mut middle) = (None, 2, false);
+    let (mut birth, mut survival, mut shape) = (None, None, 'M');
+    // Fields starting with a digit carry on the list of counts before them
+    let mut fields = Vec::<String>::new();
+    for field in rule.split(',').map(|f| f.trim()) {
+        match fields.last_mut() {
+            Some(last)
+                if field.starts_with(|c: char| c.is_ascii_digit())
+                    && (last.starts_with('S') || last.starts_with('B')) =>
+            {
+                *last += &format!(",{}", field);
+            }
+            _ => fields.push(field.to_string()),
+        }
+    }
+    for field in &fields {
+        let value = field.get(1..).unwrap_or("");
+        match field.chars().next() {
+            Some('R') => radius = Some(number(value)?),
+            Some('C') => generations = number(value)?.max(2),
+            Some('M') => middle = number(value)? == 1,
+            Some('S') => survival = Some(value),
+            Some('B') => birth = Some(value),
+            Some('N') => shape = value.chars().next().unwrap_or('M'),
+            _ => return Err(format!("unknown field {} in {}", field, rule)),
+        }
+    }
+
+    check_generations(generations)?;
+    let radius = radius.ok_or("missing radius (R)")?;
+    if radius == 0 || radius > MAX_RADIUS as usize {
+        return Err(format!("the radius must be from 1 to {}", MAX_RADIUS));
+    }
+    let radius = radius as u32;
+    let neighborhood = match shape {
+        'M' => Neighborhood::Moore(radius),
+        'N' => Neighborhood::VonNeumann(radius),
+        '+' => Neighborhood::Cross(radius),
+        _ => return Err(format!("unsupported neighborhood N{}", shape)),
+    };
+    // Counts past the whole neighborhood, and itself with M1, can't happen
+    let cells = neighborhood.offsets().len() as u32;
+    let birth = list(birth.ok_or("missing birth (B) range")?, cells)?;
+    let survival = survival.ok_or("missing survival (S) range")?;
+    let mut survival = list(survival, cells + middle as u32)?;
+
+    // With M1 a live cell counts itself, which our neighbor counts never do
+    if middle {
+        survival = survival.iter().filter_map(|n| n.checked_sub(1)).collect();
+    }
+    // Ranges stay ranges, so they read back the way they were written
+    let predicate = |counts: Vec<u32>| match (counts.first(), counts.last()) {
+        (Some(&min), Some(&max)) if (max - min) as usize + 1 == counts.len() => {
+            Some(Predicate::InRange(min, max))
+        }
+        (Some(_), _) => Some(Predicate::InSet(counts)),
+        _ => None,
+    };
+    let (birth, survival) = (predicate(birth), predicate(survival));
+    Ok((
+        generation_states(birth, survival, generations),
+        neighborhood,
+    ))
+}
+
+// Builds the states of a Generations rule; with 2 states this is a Life-like rule
+fn generation_states(
+    birth: Option<Predicate>,
+    survival: Option<Predicate>,
+    generations: usize,
+) -> Vec<State> {
+    let rule = |predicate: Option<Predicate>| {
+        predicate
+            .map(|p| vec![Rule::new(1, Condition::count(1, p))])
+            .unwrap_or_default()
+    };
+    let mut states = vec![
+        State::new(0, String::from("#000000"), 0, rule(birth)),
+        State::new(1, String::from("#FFFFFF"), 2 % generations, rule(survival)),
+    ];
+    for index in 2..generations {
+        // Dying states fade from white towards black
+        let shade = 255 * (generations - index) / (generations - 1);
+        let color = format!("#{:02X}{:02X}{:02X}", shade, shade, shade);
+        states.push(State::new(index, color, (index + 1) % generations, vec![]));
+    }
+    states
+}
+
+// Reads the birth and survival counts back out of a set of states by trying
+// every possible number of live neighbors
+fn transitions(
+    states: &[State],
+    neighborhood: &Neighborhood,
+) -> Result<(Vec<u32>, Vec<u32>), String> {
+    let count = states.len();
+    if count < 2 {
+        return Err(String::from("a rulestring needs at least 2 states"));
+    }
+    let not_expressible = || Err(String::from("these rules have no rulestring"));
+    // Dead neighbors are only known from the live count when there are no dying states
+    let counted = |state: usize| state == 1 || (state == 0 && count == 2);
+    if !is_deterministic(states) {
+        return not_expressible();
+    }
+    for state in states {
+        for rule in &state.rules {
+            if !rule.condition.count_states().into_iter().all(counted) {
+                return not_expressible();
+            }
+        }
+    }
+
+    let cells = neighborhood.offsets().len() as u32;
+    let next = |state: &State, alive: u32| {
+        let mut counts = vec![0; count];
+        counts[1] = alive;
+        if count == 2 {
+            counts[0] = cells - alive;
+        }
+        state
+            .rules
+            .iter()
+            .find(|rule| rule.condition.test(&counts))
+            .map_or(state.fail_state, |rule| rule.target_state)
+    };
+    let (mut birth, mut survival) = (vec![], vec![]);
+    for alive in 0..=cells {
+        match next(&states[0], alive) {
+            0 => {}
+            1 => birth.push(alive),
+            _ => return not_expressible(),
+        }
+        match next(&states[1], alive) {
+            1 => survival.push(alive),
+            dying if dying == 2 % count => {}
+            _ => return not_expressible(),
+        }
+        for (index, state) in states.iter().enumerate().skip(2) {
+            if next(state, alive) != (index + 1) % count {
+                return not_expressible();
+            }
+        }
+    }
+    Ok((birth, survival))
+}
+
+// A list of single digit neighbor counts, e.g. the "23" of S23
+fn counts(digits: &str, cells: u32) -> Result<Vec<u32>, String> {
+    let mut counts = vec![];
+    for digit in digits.chars() {
+        match digit.to_digit(10) {
+            Some(n) if n <= cells => counts.push(n),
+            Some(n) => {
+                return Err(format!(
+                    "{} is more than the {} cells in the neighborhood",
+                    n, cells
+                ))
+            }
+            None => return Err(format!("expected a digit, got {}", digit)),
+        }
+    }
+    counts.sort();
+    counts.dedup();
+    Ok(counts)
+}
+
+// Keeps the dying states to as many as a project can hold
+fn check_generations(generations: usize) -> Result<(), String> {
+    match (2..=MAX_STATES).contains(&generations) {
+        true => Ok(()),
+        false => Err(format!("a rule needs from 2 to {} states", MAX_STATES)),
+    }
+}
+
+fn number(text: &str) -> Result<usize, String> {
+    text.parse()
+        .map_err(|_| format!("expected a number, got {}", text))
+}
+
+// Comma separated counts and ranges, sorted, with counts over max dropped
+fn list(text: &str, max: u32) -> Result<Vec<u32>, String> {
+    let mut counts = vec![];
+    for part in text.split(',') {
+        let (min, top) = range(part)?;
+        counts.extend(min..=top.min(max));
+    }
+    counts.sort();
+    counts.dedup();
+    Ok(counts)
+}
+
+// Counts written as a list, with each run of consecutive ones as a range
+fn runs(set: &[u32]) -> String {
+    let mut parts = vec![];
+    let mut start = 0;
+    for i in 1..=set.len() {
+        if i == set.len() || set[i] != set[i - 1] + 1 {
+            parts.push(match i - start {
+                1 => set[start].to_string(),
+                _ => format!("{}-{}", set[start], set[i - 1]),
+            });
+            start = i;
+        }
+    }
+    parts.join(",")
+}
+
+// An inclusive range written "min..max" or "min-max"
+fn range(text: &str) -> Result<(u32, u32), String> {
+    let (min, max) = text
+        .split_once("..")
+        .or_else(|| text.split_once('-'))
+        .unwrap_or((text, text));
+    Ok((number(min)? as u32, number(max)? as u32))
+}
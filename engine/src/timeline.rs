@@ -0,0 +1,157 @@
+use crate::life::{Cell, Grid};
+use std::collections::VecDeque;
+use std::mem::{size_of, size_of_val};
+
+pub const DEFAULT_CAPACITY: usize = 1000;
+const KEYFRAME_INTERVAL: usize = 32;
+
+// A full grid followed by the cells that changed in each generation after it,
+// as (index, state) pairs
+struct Segment {
+    keyframe: Grid,
+    deltas: Vec<Vec<(usize, Cell)>>,
+}
+
+// The last capacity generations of a simulation, so it can be stepped
+// backwards and scrubbed through. Most generations only keep the cells that
+// changed, with a full grid every so often to rebuild them from.
+pub struct Timeline {
+    segments: VecDeque<Segment>,
+    first: usize,
+    position: usize,
+    current: Grid,
+    capacity: usize,
+}
+
+impl Segment {
+    fn new(keyframe: Grid) -> Self {
+        Segment {
+            keyframe,
+            deltas: vec![],
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.deltas.len() + 1
+    }
+}
+
+impl Timeline {
+    pub fn new(grid: Grid, capacity: usize) -> Self {
+        Timeline {
+            segments: VecDeque::from([Segment::new(grid.clone())]),
+            first: 0,
+            position: 0,
+            current: grid,
+            capacity: capacity.max(1),
+        }
+    }
+
+    // The oldest generation still kept, the newest, and the one being shown
+    pub fn first(&self) -> usize {
+        self.first
+    }
+
+    pub fn last(&self) -> usize {
+        self.first + self.segments.iter().map(|s| s.len()).sum::<usize>() - 1
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // Records after as the generation that follows before. Stepping from an
+    // earlier generation forgets the ones that came after it, and stepping from
+    // a grid the timeline hasn't seen, e.g. one that was edited, starts over.
+    pub fn record(&mut self, before: &Grid, after: &Grid) {
+        if *before != self.current {
+            *self = Timeline::new(before.clone(), self.capacity);
+        }
+        self.truncate();
+
+        let same_size = before.width() == after.width() && before.height() == after.height();
+        let mut delta = vec![];
+        if same_size {
+            for (i, (&old, &new)) in before.cells().iter().zip(after.cells()).enumerate() {
+                if old != new {
+                    delta.push((i, new));
+                }
+            }
+        }
+        // Busy generations take less room kept whole
+        let whole = size_of_val(after.cells());
+        let segment = self.segments.back_mut().unwrap();
+        if !same_size
+            || segment.len() >= KEYFRAME_INTERVAL
+            || delta.len() * size_of::<(usize, Cell)>() > whole
+        {
+            self.segments.push_back(Segment::new(after.clone()));
+        } else {
+            segment.deltas.push(delta);
+        }
+        self.position += 1;
+        self.current = after.clone();
+
+        // The oldest generation is folded into the keyframe, so every delta
+        // left still has one to build on
+        while self.last() - self.first + 1 > self.capacity {
+            let segment = self.segments.front_mut().unwrap();
+            if segment.deltas.is_empty() {
+                self.segments.pop_front();
+            } else {
+                for (i, state) in segment.deltas.remove(0) {
+                    segment.keyframe.cells_mut()[i] = state;
+                }
+            }
+            self.first += 1;
+        }
+    }
+
+    // Starts over from a grid that is generations ahead of the current one,
+    // for jumps too long to record every generation of
+    pub fn skip(&mut self, grid: &Grid, generations: usize) {
+        let position = self.position.saturating_add(generations);
+        *self = Timeline::new(grid.clone(), self.capacity);
+        self.first = position;
+        self.position = position;
+    }
+
+    // Moves to a recorded generation and returns its grid
+    pub fn seek(&mut self, generation: usize) -> Option<&Grid> {
+        if generation < self.first || generation > self.last() {
+            return None;
+        }
+        let mut start = self.first;
+        for segment in &self.segments {
+            if generation < start + segment.len() {
+                let mut grid = segment.keyframe.clone();
+                for delta in &segment.deltas[..generation - start] {
+                    for &(i, state) in delta {
+                        grid.cells_mut()[i] = state;
+                    }
+                }
+                self.position = generation;
+                self.current = grid;
+                break;
+            }
+            start += segment.len();
+        }
+        Some(&self.current)
+    }
+
+    // Forgets every generation after the current one
+    fn truncate(&mut self) {
+        let mut start = self.first;
+        let mut keep = 0;
+        for segment in &self.segments {
+            keep += 1;
+            if self.position < start + segment.len() {
+                break;
+            }
+            start += segment.len();
+        }
+        self.segments.truncate(keep);
+        let segment = self.segments.back_mut().unwrap();
+        segment.deltas.truncate(self.position - start);
+    }
+}
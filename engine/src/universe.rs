@@ -0,0 +1,214 @@
+use crate::life::*;
+use crate::random::Random;
+use std::collections::{HashMap, HashSet};
+
+// The universe is split into square chunks of this many cells a side
+pub const CHUNK: usize = 32;
+
+type Key = (i64, i64);
+
+// An unbounded plane of cells where state 0 is empty. Only chunks holding a
+// non-zero cell are stored, and only chunks near something that changed in
+// the last generation are stepped, since everywhere else is known to be still.
+// Rules left to chance can change a still chunk, so with those every stored
+// chunk is stepped.
+pub struct Universe {
+    state_types: Vec<State>,
+    neighborhood: Neighborhood,
+    chunks: HashMap<Key, Vec<Cell>>,
+    active: HashSet<Key>,
+    pub random: Random,
+}
+
+impl Universe {
+    pub fn new(state_types: Vec<State>, neighborhood: Neighborhood) -> Self {
+        Universe {
+            state_types,
+            neighborhood,
+            chunks: HashMap::new(),
+            active: HashSet::new(),
+            random: Random::new(0),
+        }
+    }
+
+    // A universe holding the board's rules and cells, with the board's top
+    // left corner at (0, 0). The board's edges are ignored.
+    pub fn from_board(board: &Board) -> Self {
+        let mut universe = Universe::new(board.state_types.clone(), board.neighborhood.clone());
+        universe.paste(&board.grid, 0, 0);
+        universe.random = board.random;
+        universe
+    }
+
+    // New rules can wake any part of the universe
+    pub fn set_rules(&mut self, state_types: &[State], neighborhood: &Neighborhood) {
+        if self.state_types != state_types || self.neighborhood != *neighborhood {
+            self.state_types = state_types.to_vec();
+            self.neighborhood = neighborhood.clone();
+            self.active = self.chunks.keys().copied().collect();
+        }
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> usize {
+        let (key, i) = locate(x, y);
+        self.chunks.get(&key).map_or(0, |chunk| chunk[i] as usize)
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, state: usize) {
+        let (key, i) = locate(x, y);
+        if self.get(x, y) == state {
+            return;
+        }
+        let chunk = self
+            .chunks
+            .entry(key)
+            .or_insert_with(|| vec![0; CHUNK * CHUNK]);
+        chunk[i] = state as Cell;
+        if state == 0 && chunk.iter().all(|&c| c == 0) {
+            self.chunks.remove(&key);
+        }
+        self.active.insert(key);
+    }
+
+    // Copies a grid into the universe with its top left corner at (x, y)
+    pub fn paste(&mut self, grid: &Grid, x: i64, y: i64) {
+        for dy in 0..grid.height() {
+            for dx in 0..grid.width() {
+                self.set(x + dx as i64, y + dy as i64, grid.get(dx, dy));
+            }
+        }
+    }
+
+    pub fn region(&self, x: i64, y: i64, width: usize, height: usize) -> Grid {
+        let mut region = Grid::new(width, height);
+        for dy in 0..height {
+            // Copy the row a chunk's worth at a time
+            let mut dx = 0;
+            while dx < width {
+                let (key, i) = locate(x + dx as i64, y + dy as i64);
+                let run = (CHUNK - i % CHUNK).min(width - dx);
+                if let Some(chunk) = self.chunks.get(&key) {
+                    let start = dy * width + dx;
+                    region.cells_mut()[start..start + run].copy_from_slice(&chunk[i..i + run]);
+                }
+                dx += run;
+            }
+        }
+        region
+    }
+
+    // The smallest (x, y, width, height) rectangle holding every non-zero cell
+    pub fn bounding_box(&self) -> Option<(i64, i64, usize, usize)> {
+        let mut bounds: Option<(i64, i64, i64, i64)> = None;
+        for (&(cx, cy), chunk) in &self.chunks {
+            for (i, _) in chunk.iter().enumerate().filter(|(_, &c)| c != 0) {
+                let x = cx * CHUNK as i64 + (i % CHUNK) as i64;
+                let y = cy * CHUNK as i64 + (i / CHUNK) as i64;
+                bounds = Some(match bounds {
+                    Some((left, top, right, bottom)) => {
+                        (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                    }
+                    None => (x, y, x, y),
+                });
+            }
+        }
+        let (left, top, right, bottom) = bounds?;
+        Some((
+            left,
+            top,
+            (right - left + 1) as usize,
+            (bottom - top + 1) as usize,
+        ))
+    }
+
+    // How many chunks are stored, which grows with the pattern
+    pub fn chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    // Fails for rules that bring empty space to life, which would fill the
+    // whole unbounded plane at once
+    pub fn step(&mut self) -> Result<(), String> {
+        let offsets = self.neighborhood.offsets();
+        let mut counts = vec![0; self.state_types.len()];
+        counts[self.state_types[0].index] = offsets.len() as u32;
+        if can_change(&self.state_types, 0, &counts) {
+            return Err(String::from(
+                "an unbounded universe needs state 0 to stay state 0 when surrounded by state 0",
+            ));
+        }
+
+        // How far outside a chunk its cells can see, in cells and in chunks
+        let reach = offsets
+            .iter()
+            .map(|&(dx, dy)| dx.unsigned_abs().max(dy.unsigned_abs()) as usize)
+            .max()
+            .unwrap_or(0);
+        let span = reach.div_ceil(CHUNK) as i64;
+        let mut candidates = HashSet::new();
+        let awake = match is_deterministic(&self.state_types) {
+            true => self.active.iter().copied().collect::<Vec<_>>(),
+            false => self.chunks.keys().copied().collect(),
+        };
+        for (cx, cy) in awake {
+            for dy in -span..=span {
+                for dx in -span..=span {
+                    candidates.insert((cx + dx, cy + dy));
+                }
+            }
+        }
+
+        // Chunks go in order so the dice fall the same way every run
+        let mut candidates = candidates.into_iter().collect::<Vec<_>>();
+        candidates.sort_unstable();
+
+        let size = CHUNK + 2 * reach;
+        let mut updates = vec![];
+        for (cx, cy) in candidates {
+            let (left, top) = (
+                cx * CHUNK as i64 - reach as i64,
+                cy * CHUNK as i64 - reach as i64,
+            );
+            let window = self.region(left, top, size, size);
+            let mut next = vec![0; CHUNK * CHUNK];
+            for y in 0..CHUNK {
+                for x in 0..CHUNK {
+                    counts.fill(0);
+                    for &(dx, dy) in &offsets {
+                        let (wx, wy) = ((x + reach) as i32 + dx, (y + reach) as i32 + dy);
+                        let neighbor = window.get(wx as usize, wy as usize);
+                        counts[self.state_types[neighbor].index] += 1;
+                    }
+                    let state = window.get(x + reach, y + reach);
+                    next[y * CHUNK + x] =
+                        transition(&self.state_types, state, &counts, &mut self.random) as Cell;
+                }
+            }
+            let changed = match self.chunks.get(&(cx, cy)) {
+                Some(chunk) => *chunk != next,
+                None => next.iter().any(|&c| c != 0),
+            };
+            if changed {
+                updates.push(((cx, cy), next));
+            }
+        }
+
+        self.active = updates.iter().map(|&(key, _)| key).collect();
+        for (key, next) in updates {
+            if next.iter().all(|&c| c == 0) {
+                self.chunks.remove(&key);
+            } else {
+                self.chunks.insert(key, next);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The chunk holding a cell and the cell's index within it
+fn locate(x: i64, y: i64) -> (Key, usize) {
+    let size = CHUNK as i64;
+    let key = (x.div_euclid(size), y.div_euclid(size));
+    let i = y.rem_euclid(size) as usize * CHUNK + x.rem_euclid(size) as usize;
+    (key, i)
+}
@@ -0,0 +1,73 @@
+// How far in and out the view can zoom, in pixels per cell
+pub const MIN_CELL_SIZE: f64 = 1.0;
+pub const MAX_CELL_SIZE: f64 = 128.0;
+
+// Which part of the board the canvas shows. (x, y) is the canvas pixel that
+// the top left corner of cell (0, 0) lands on, so panning and zooming never
+// touch the board itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f64,
+    pub y: f64,
+    pub cell_size: f64,
+}
+
+impl Viewport {
+    pub fn new(cell_size: f64) -> Self {
+        Viewport {
+            x: 0.0,
+            y: 0.0,
+            cell_size: cell_size.clamp(MIN_CELL_SIZE, MAX_CELL_SIZE),
+        }
+    }
+
+    // The cell under a canvas pixel, which may be off the board
+    pub fn cell(&self, px: f64, py: f64) -> (i64, i64) {
+        (
+            ((px - self.x) / self.cell_size).floor() as i64,
+            ((py - self.y) / self.cell_size).floor() as i64,
+        )
+    }
+
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+    }
+
+    // Scales the cells while keeping whatever is under (px, py) where it is
+    pub fn zoom(&mut self, factor: f64, px: f64, py: f64) {
+        let cell_size = (self.cell_size * factor).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+        let scale = cell_size / self.cell_size;
+        self.x = px - (px - self.x) * scale;
+        self.y = py - (py - self.y) * scale;
+        self.cell_size = cell_size;
+    }
+
+    // Centers an (x, y, width, height) rectangle of cells on the canvas, as
+    // large as will fit
+    pub fn fit(&mut self, cells: (usize, usize, usize, usize), width: f64, height: f64) {
+        let (x, y, w, h) = cells;
+        let (w, h) = (w.max(1) as f64, h.max(1) as f64);
+        self.cell_size = (width / w)
+            .min(height / h)
+            .clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+        self.x = (width - w * self.cell_size) / 2.0 - x as f64 * self.cell_size;
+        self.y = (height - h * self.cell_size) / 2.0 - y as f64 * self.cell_size;
+    }
+
+    // The columns and rows of a width x height board that are at least partly
+    // on a canvas of the given size, as half open ranges
+    pub fn visible(
+        &self,
+        canvas: (f64, f64),
+        board: (usize, usize),
+    ) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let clip = |pixel: f64, limit: usize| pixel.clamp(0.0, limit as f64) as usize;
+        let (left, top) = self.cell(0.0, 0.0);
+        let (right, bottom) = self.cell(canvas.0 - 1.0, canvas.1 - 1.0);
+        (
+            clip(left as f64, board.0)..clip(right as f64 + 1.0, board.0),
+            clip(top as f64, board.1)..clip(bottom as f64 + 1.0, board.1),
+        )
+    }
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use ca_engine::life::*;
+use ca_engine::random::Random;
+use common::random_board;
+
+// Runs the board with and without the fast path, comparing every generation
+fn assert_same(mut fast: Board, generations: usize) {
+    let mut generic = fast.clone();
+    for generation in 1..=generations {
+        fast.step();
+        generic.step_generic();
+        assert!(
+            fast.grid == generic.grid,
+            "the fast path differs from the generic one at generation {}",
+            generation
+        );
+    }
+}
+
+#[test]
+fn matches_generic_across_word_boundaries() {
+    let mut random = Random::new(0x9E3779B97F4A7C15);
+    for (width, height) in [(1, 1), (3, 2), (62, 5), (63, 7), (64, 3), (65, 4), (130, 9)] {
+        assert_same(random_board("B3/S23", width, height, &mut random), 12);
+    }
+}
+
+#[test]
+fn matches_generic_for_many_rules() {
+    let mut random = Random::new(42);
+    for rule in [
+        "B3/S23",
+        "B36/S23",
+        "B2/S",
+        "B/S012345678",
+        "B012345678/S",
+        "B1357/S1357",
+        "B3678/S34678",
+        "B0/S8",
+    ] {
+        assert_same(random_board(rule, 70, 20, &mut random), 10);
+    }
+}
+
+#[test]
+fn matches_generic_on_every_boundary() {
+    let mut random = Random::new(7);
+    let boundaries = [
+        Boundary::Wrap,
+        Boundary::Twist,
+        Boundary::Reflect,
+        Boundary::Dead,
+        Boundary::State(1),
+    ];
+    for x in &boundaries {
+        for y in &boundaries {
+            let mut board = random_board("B36/S23", 67, 13, &mut random);
+            board.boundary_x = x.clone();
+            board.boundary_y = y.clone();
+            assert_same(board, 8);
+        }
+    }
+}
+
+#[test]
+fn matches_generic_for_hand_written_rules() {
+    // Rules that count dead cells, or combine counts, are still two state
+    let mut board = random_board("B3/S23", 40, 30, &mut Random::new(99));
+    board.state_types[0].rules = vec![Rule::new(
+        1,
+        Condition::And(vec![
+            Condition::count(0, Predicate::LessEqual(6)),
+            Condition::Not(Box::new(Condition::count(1, Predicate::Equal(4)))),
+        ]),
+    )];
+    board.state_types[1].rules = vec![Rule::new(1, Condition::count(1, Predicate::InRange(1, 4)))];
+    assert_same(board, 10);
+}
@@ -0,0 +1,74 @@
+use ca_engine::brush::*;
+use ca_engine::life::*;
+
+#[test]
+fn lines_are_unbroken() {
+    for to in [(7, 3), (-5, 9), (0, -6), (4, 4), (0, 0)] {
+        let points = line((0, 0), to);
+        assert_eq!(points.first(), Some(&(0, 0)));
+        assert_eq!(points.last(), Some(&to));
+        for pair in points.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert!((a.0 - b.0).abs() <= 1 && (a.1 - b.1).abs() <= 1);
+        }
+    }
+}
+
+#[test]
+fn shapes_cover_the_right_cells() {
+    assert_eq!(rectangle((3, 1), (0, 4), true).len(), 16);
+    assert_eq!(rectangle((3, 1), (0, 4), false).len(), 12);
+    assert_eq!(circle((5, 5), (5, 5), false), vec![(5, 5)]);
+    assert_eq!(circle((0, 0), (1, 0), false).len(), 8);
+
+    let outline = circle((0, 0), (0, 6), false);
+    let filled = circle((0, 0), (6, 0), true);
+    assert!(outline.iter().all(|p| filled.contains(p)));
+    assert!(!outline.contains(&(0, 0)) && filled.contains(&(0, 0)));
+    for &(x, y) in &outline {
+        assert!(outline.contains(&(-x, y)) && outline.contains(&(y, x)));
+    }
+}
+
+#[test]
+fn brushes_paint_squares_clipped_to_the_grid() {
+    let mut grid = Grid::new(6, 6);
+    paint(&mut grid, &[(0, 0)], 3, 1);
+    assert_eq!(grid.bounding_box(), Some((0, 0, 2, 2)));
+    paint(&mut grid, &[(4, 4)], 2, 2);
+    assert_eq!(grid.get(5, 5), 2);
+    assert_eq!(grid.region(4, 4, 2, 2).cells(), &[2, 2, 2, 2]);
+    paint(&mut grid, &Tool::Line.shape((0, 0), (5, 5)), 1, 0);
+    assert_eq!(grid.get(4, 4), 0);
+    assert_eq!(grid.get(4, 5), 2);
+}
+
+#[test]
+fn wide_brushes_cover_filled_shapes_like_a_stamp_at_every_point() {
+    for (points, size) in [
+        (circle((20, 20), (29, 20), true), 6),
+        (rectangle((3, 5), (30, 12), true), 5),
+        (circle((1, 38), (7, 38), true), 9),
+    ] {
+        let mut grid = Grid::new(40, 40);
+        paint(&mut grid, &points, size, 1);
+        let mut stamped = Grid::new(40, 40);
+        for &point in &points {
+            paint(&mut stamped, &[point], size, 1);
+        }
+        assert_eq!(grid, stamped);
+    }
+}
+
+#[test]
+fn flood_fill_stays_inside_walls() {
+    let mut grid = Grid::new(7, 7);
+    paint(&mut grid, &rectangle((1, 1), (5, 5), false), 1, 1);
+    flood_fill(&mut grid, 3, 3, 2);
+    assert_eq!(grid.region(2, 2, 3, 3).cells(), &[2; 9]);
+    assert_eq!(grid.get(0, 0), 0);
+    assert_eq!(grid.get(1, 1), 1);
+
+    flood_fill(&mut grid, 0, 0, 3);
+    assert_eq!(grid.cells().iter().filter(|&&c| c == 3).count(), 49 - 25);
+}
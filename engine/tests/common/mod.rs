@@ -0,0 +1,15 @@
+use ca_engine::life::*;
+use ca_engine::random::Random;
+use ca_engine::rulestring;
+
+// A board running the rule with each cell alive or dead by a coin toss. The
+// generator is seeded, so the boards are the same every run.
+pub fn random_board(rule: &str, width: usize, height: usize, random: &mut Random) -> Board {
+    let (states, neighborhood) = rulestring::parse(rule).unwrap();
+    let mut board = Board::new(width, height, Some(states));
+    board.neighborhood = neighborhood;
+    for cell in board.grid.cells_mut() {
+        *cell = random.chance(0.5) as Cell;
+    }
+    board
+}
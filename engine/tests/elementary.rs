@@ -0,0 +1,71 @@
+use ca_engine::elementary::{self, Rule, Spacetime};
+
+fn picture(spacetime: &Spacetime) -> Vec<String> {
+    spacetime
+        .grid()
+        .rows()
+        .take(spacetime.rows())
+        .map(|row| {
+            row.iter()
+                .map(|&c| if c == 0 { '.' } else { '#' })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn elementary_rules_from_a_single_cell() {
+    let mut rule_30 = Spacetime::new(Rule::Elementary(30), &elementary::single_cell(9), 8);
+    let mut rule_90 = Spacetime::new(Rule::Elementary(90), &elementary::single_cell(9), 8);
+    for _ in 0..3 {
+        rule_30.step();
+        rule_90.step();
+    }
+    assert_eq!(
+        picture(&rule_30),
+        ["....#....", "...###...", "..##..#..", ".##.####."]
+    );
+    assert_eq!(
+        picture(&rule_90),
+        ["....#....", "...#.#...", "..#...#..", ".#.#.#.#."]
+    );
+}
+
+#[test]
+fn totalistic_codes_match_their_elementary_rule() {
+    // Rule 150 sets a cell when an odd number of the three are set
+    let totalistic = Rule::totalistic(2, 1, 0b1010).unwrap();
+    let row = [1, 0, 0, 1, 1, 0, 1, 0, 1, 1, 1, 0, 0, 0, 1, 0];
+    let (mut a, mut b) = (row.to_vec(), row.to_vec());
+    for _ in 0..20 {
+        a = Rule::Elementary(150).step(&a);
+        b = totalistic.step(&b);
+        assert_eq!(a, b);
+    }
+
+    // Three colors and radius one have seven sums, so 3^7 codes
+    assert!(Rule::totalistic(3, 1, 2186).is_ok());
+    assert!(Rule::totalistic(3, 1, 2187).is_err());
+    assert!(Rule::totalistic(1, 1, 0).is_err());
+    assert!(Rule::totalistic(2, 0, 0).is_err());
+    let cells = Rule::totalistic(3, 1, 1635).unwrap().step(&[0, 2, 1, 0, 0]);
+    assert!(cells.iter().all(|&c| c < 3));
+}
+
+#[test]
+fn spacetime_scrolls_once_full() {
+    let mut spacetime = Spacetime::new(Rule::Elementary(110), &elementary::single_cell(16), 4);
+    let mut rows = vec![spacetime.row().to_vec()];
+    for _ in 0..10 {
+        spacetime.step();
+        rows.push(spacetime.row().to_vec());
+    }
+    assert_eq!(spacetime.generation, 10);
+    assert_eq!(spacetime.rows(), 4);
+    let shown = spacetime
+        .grid()
+        .rows()
+        .map(<[_]>::to_vec)
+        .collect::<Vec<_>>();
+    assert_eq!(shown, rows[7..]);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use ca_engine::hashlife;
+use ca_engine::life::*;
+use ca_engine::random::Random;
+use common::random_board;
+
+#[test]
+fn jumps_match_stepping() {
+    for (rule, width, height) in [
+        ("B3/S23", 16, 8),
+        ("B3/S23", 13, 7),
+        ("B36/S23", 32, 16),
+        ("B2/S", 9, 20),
+        ("B0/S8", 5, 5),
+        ("B3/S23", 1, 1),
+    ] {
+        for log2 in 0..7 {
+            let mut stepped =
+                random_board(rule, width, height, &mut Random::new(0x2545F4914F6CDD1D));
+            let mut jumped = stepped.clone();
+            for _ in 0..1 << log2 {
+                stepped.step();
+            }
+            hashlife::jump(&mut jumped, log2).unwrap();
+            assert!(
+                stepped.grid == jumped.grid,
+                "{} on {}x{} differs after 2^{} generations",
+                rule,
+                width,
+                height,
+                log2
+            );
+        }
+    }
+}
+
+#[test]
+fn glider_comes_home_after_a_million_generations() {
+    // On a 16x16 torus a glider is back where it started every 64 generations
+    let mut board = Board::new(16, 16, None);
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+        board.grid.set(x, y, 1);
+    }
+    let start = board.grid.clone();
+    hashlife::jump(&mut board, 20).unwrap();
+    assert_eq!(board.grid, start);
+}
+
+#[test]
+fn rejects_boards_it_cannot_run() {
+    let mut board = Board::new(8, 8, None);
+    board.boundary_x = Boundary::Dead;
+    assert!(hashlife::jump(&mut board, 3).is_err());
+    let mut board = random_board("B2/S/C3", 8, 8, &mut Random::new(1));
+    assert!(hashlife::jump(&mut board, 3).is_err());
+}
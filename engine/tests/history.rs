@@ -0,0 +1,116 @@
+use ca_engine::history::History;
+use ca_engine::life::*;
+
+#[test]
+fn edits_on_a_grid_that_has_shrunk_are_skipped() {
+    let mut history = History::new(1 << 20);
+    let before = Board::new(10, 10, None);
+    let mut board = before.clone();
+    board.grid.set(5, 4, 1);
+    history.record(&before, &board);
+
+    board.grid = Grid::new(4, 4);
+    assert!(history.undo(&mut board));
+    assert!(history.redo(&mut board));
+    assert_eq!(board.grid, Grid::new(4, 4));
+
+    history.clear();
+    assert!(!history.can_undo() && !history.can_redo());
+}
+
+// A blank board and the same board with the cells set to the state
+fn painted(cells: &[(usize, usize)], state: usize) -> (Board, Board) {
+    let before = Board::new(8, 8, None);
+    let mut after = before.clone();
+    for &(x, y) in cells {
+        after.grid.set(x, y, state);
+    }
+    (before, after)
+}
+
+#[test]
+fn undo_and_redo_go_back_and_forth() {
+    let mut history = History::new(1 << 20);
+    let (blank, mut board) = painted(&[(1, 1), (2, 1)], 1);
+    history.record(&blank, &board);
+    let mut bigger = board.clone();
+    bigger.grid = board.grid.resized(10, 9, Anchor::TopLeft);
+    bigger.state_types[0].color = String::from("#123456");
+    history.record(&board, &bigger);
+    let painted = board.clone();
+    board = bigger.clone();
+
+    assert!(history.undo(&mut board));
+    assert_eq!(board.grid, painted.grid);
+    assert_eq!(board.state_types, painted.state_types);
+    assert!(history.undo(&mut board));
+    assert_eq!(board.grid, blank.grid);
+    assert!(!history.undo(&mut board));
+
+    assert!(history.redo(&mut board));
+    assert!(history.redo(&mut board));
+    assert_eq!(board.grid, bigger.grid);
+    assert_eq!(board.state_types, bigger.state_types);
+    assert!(!history.redo(&mut board));
+}
+
+#[test]
+fn a_new_edit_forgets_what_could_be_redone() {
+    let mut history = History::new(1 << 20);
+    let (blank, mut board) = painted(&[(3, 3)], 1);
+    history.record(&blank, &board);
+    history.undo(&mut board);
+    assert!(history.can_redo());
+
+    let (_, other) = painted(&[(5, 5)], 1);
+    history.record(&board, &other);
+    assert!(!history.can_redo());
+    assert!(!history.redo(&mut board));
+}
+
+#[test]
+fn the_oldest_edits_are_dropped_over_budget() {
+    let (blank, board) = painted(&[(0, 0)], 1);
+    let mut history = History::new(1 << 20);
+    history.record(&blank, &board);
+    let one = history.used();
+
+    let mut history = History::new(3 * one);
+    let mut board = blank.clone();
+    for x in 0..5 {
+        let before = board.clone();
+        board.grid.set(x, 0, 1);
+        history.record(&before, &board);
+    }
+    let mut undone = 0;
+    while history.undo(&mut board) {
+        undone += 1;
+    }
+    assert_eq!(undone, 3);
+    assert_eq!(board.grid.get(1, 0), 1);
+    assert_eq!(board.grid.get(2, 0), 0);
+
+    history.set_budget(0);
+    assert!(!history.can_undo() && !history.can_redo());
+}
+
+#[test]
+fn undo_after_a_run_only_puts_back_the_edited_cells() {
+    let mut history = History::new(1 << 20);
+    let mut board = Board::new(8, 8, None);
+    for (x, y) in [(1, 2), (2, 2), (3, 2)] {
+        board.grid.set(x, y, 1);
+    }
+    let before = board.clone();
+    board.grid.set(6, 6, 1);
+    history.record(&before, &board);
+
+    // The blinker turns while the painted cell dies of loneliness
+    board.step();
+    history.undo(&mut board);
+    let mut expected = Grid::new(8, 8);
+    for (x, y) in [(2, 1), (2, 2), (2, 3)] {
+        expected.set(x, y, 1);
+    }
+    assert_eq!(board.grid, expected);
+}
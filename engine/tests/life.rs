@@ -0,0 +1,362 @@
+use ca_engine::life::*;
+use ca_engine::project::Project;
+use ca_engine::random::Random;
+use ca_engine::{hashlife, rle};
+
+// A Game of Life board with the pattern pasted at (x, y)
+fn board(width: usize, height: usize, pattern: &str, x: i32, y: i32) -> Board {
+    let mut board = Board::new(width, height, None);
+    board.grid.paste(&rle::parse(pattern).unwrap().cells, x, y);
+    board
+}
+
+fn step(board: &mut Board, generations: usize) {
+    for _ in 0..generations {
+        board.step();
+    }
+}
+
+const BLINKER: &str = "x = 3, y = 1\n3o!";
+const BLOCK: &str = "x = 2, y = 2\n2o$2o!";
+const GLIDER: &str = "x = 3, y = 3\nbob$2bo$3o!";
+
+#[test]
+fn block_is_still() {
+    let mut b = board(6, 6, BLOCK, 2, 2);
+    let start = b.grid.clone();
+    step(&mut b, 1);
+    assert_eq!(b.grid, start);
+}
+
+#[test]
+fn blinker_oscillates() {
+    let mut b = board(5, 5, BLINKER, 1, 2);
+    let start = b.grid.clone();
+    step(&mut b, 1);
+    assert_eq!(b.grid, board(5, 5, "x = 1, y = 3\no$o$o!", 2, 1).grid);
+    step(&mut b, 1);
+    assert_eq!(b.grid, start);
+}
+
+#[test]
+fn glider_moves_diagonally() {
+    let mut b = board(10, 10, GLIDER, 1, 1);
+    step(&mut b, 4);
+    assert_eq!(b.grid, board(10, 10, GLIDER, 2, 2).grid);
+}
+
+#[test]
+fn blinker_wraps_around_the_edges() {
+    // Split across the left and right edges, and across the top and bottom
+    let mut b = board(5, 5, "x = 2, y = 1\n2o!", 0, 2);
+    b.grid.set(4, 2, 1);
+    step(&mut b, 1);
+    let mut vertical = Board::new(5, 5, None);
+    for y in [1, 2, 3] {
+        vertical.grid.set(0, y, 1);
+    }
+    assert_eq!(b.grid, vertical.grid);
+
+    let mut b = board(5, 5, "x = 1, y = 2\no$o!", 2, 0);
+    b.grid.set(2, 4, 1);
+    step(&mut b, 1);
+    assert_eq!(b.grid, board(5, 5, BLINKER, 1, 0).grid);
+}
+
+#[test]
+fn glider_wraps_back_to_its_start() {
+    // A glider moves one cell diagonally every 4 generations
+    let mut b = board(8, 8, GLIDER, 5, 5);
+    let start = b.grid.clone();
+    step(&mut b, 4 * 8);
+    assert_eq!(b.grid, start);
+}
+
+#[test]
+fn dead_edges_stop_wrapping() {
+    let mut b = board(5, 5, "x = 2, y = 1\n2o!", 0, 2);
+    b.grid.set(4, 2, 1);
+    b.boundary_x = Boundary::Dead;
+    b.boundary_y = Boundary::Dead;
+    step(&mut b, 1);
+    assert!(b.grid.cells().iter().all(|&c| c == 0));
+}
+
+#[test]
+fn cells_off_the_board_follow_the_boundary() {
+    let mut b = Board::new(4, 3, None);
+    b.grid.set(0, 0, 1);
+    b.grid.set(1, 2, 1);
+    assert_eq!(b.cell_at(4, 0), 1);
+    assert_eq!(b.cell_at(-1, 0), 0);
+    b.boundary_x = Boundary::Reflect;
+    assert_eq!(b.cell_at(-1, 0), 1);
+    b.boundary_x = Boundary::State(1);
+    assert_eq!(b.cell_at(-1, 2), 1);
+    b.boundary_x = Boundary::Twist;
+    assert_eq!(b.cell_at(4, 0), 0);
+    assert_eq!(b.cell_at(4, 2), 1);
+}
+
+#[test]
+fn population_counts_every_state() {
+    let mut b = board(6, 6, GLIDER, 1, 1);
+    assert_eq!(b.population(), vec![31, 5]);
+    step(&mut b, 3);
+    assert_eq!(b.population(), vec![31, 5]);
+}
+
+#[test]
+fn grid_regions_paste_back() {
+    let b = board(8, 6, GLIDER, 3, 2);
+    assert_eq!(b.grid.bounding_box(), Some((3, 2, 3, 3)));
+    let glider = b.grid.region(3, 2, 3, 3);
+    assert_eq!(glider, rle::parse(GLIDER).unwrap().cells);
+
+    let mut moved = Grid::new(8, 6);
+    moved.paste(&glider, 6, -1);
+    assert_eq!(moved.bounding_box(), Some((6, 1, 2, 1)));
+}
+
+#[test]
+fn resizing_keeps_cells_around_the_anchor() {
+    let b = board(8, 6, GLIDER, 3, 2);
+    let grown = b.grid.resized(12, 9, Anchor::Center);
+    assert_eq!(grown.bounding_box(), Some((5, 3, 3, 3)));
+    assert_eq!(grown.resized(8, 6, Anchor::Center), b.grid);
+
+    let cropped = b.grid.resized(5, 4, Anchor::TopLeft);
+    assert_eq!(cropped.bounding_box(), Some((4, 2, 1, 1)));
+    assert_eq!(
+        b.grid.resized(5, 4, Anchor::Center).bounding_box(),
+        Some((2, 1, 3, 3))
+    );
+}
+
+#[test]
+fn grids_rotate_and_flip() {
+    let glider = rle::parse(GLIDER).unwrap().cells;
+    let turned = Grid::from_rows(&[vec![1, 0, 0], vec![1, 0, 1], vec![1, 1, 0]]);
+    assert_eq!(glider.rotated(), turned);
+    assert_eq!(glider.rotated().rotated().rotated().rotated(), glider);
+    assert_eq!(
+        glider.flipped_horizontally(),
+        Grid::from_rows(&[vec![0, 1, 0], vec![1, 0, 0], vec![1, 1, 1]])
+    );
+    assert_eq!(
+        glider.flipped_vertically(),
+        Grid::from_rows(&[vec![1, 1, 1], vec![0, 0, 1], vec![0, 1, 0]])
+    );
+
+    let wide = Grid::from_rows(&[vec![1, 2, 3, 4]]);
+    assert_eq!(wide.rotated().width(), 1);
+    assert_eq!(wide.rotated().cells(), &[1, 2, 3, 4]);
+
+    let mut filled = Grid::new(4, 4);
+    filled.fill(1, 2, 3, 2, || 2);
+    assert_eq!(filled.bounding_box(), Some((1, 2, 3, 2)));
+}
+
+// Game of Life on a soup, where births only happen half the time
+fn noisy_life(seed: u64) -> Board {
+    let mut b = Board::new(24, 24, None);
+    b.state_types[0].rules[0].probability = 0.5;
+    let mut random = Random::new(99);
+    b.grid.fill(0, 0, 24, 24, || random.chance(0.3) as usize);
+    b.reseed(seed);
+    b
+}
+
+#[test]
+fn chance_repeats_from_the_same_seed() {
+    let run = |seed| {
+        let mut b = noisy_life(seed);
+        step(&mut b, 10);
+        b.grid
+    };
+    assert_eq!(run(1), run(1));
+    assert_ne!(run(1), run(2));
+
+    // Saved partway through, a board carries on as if it hadn't stopped
+    let mut b = noisy_life(7);
+    step(&mut b, 5);
+    let json = Project::new(&b, 1000).to_json();
+    let mut loaded = Project::from_json(&json).unwrap().into_board();
+    step(&mut b, 5);
+    step(&mut loaded, 5);
+    assert_eq!(loaded.grid, b.grid);
+}
+
+#[test]
+fn rules_left_to_chance_skip_the_fast_paths() {
+    let mut b = noisy_life(3);
+    assert!(!b.is_deterministic());
+    assert!(hashlife::jump(&mut b, 2).is_err());
+    assert!(Board::new(4, 4, None).is_deterministic());
+}
+
+#[test]
+fn certain_and_impossible_chances() {
+    // Without births a blinker is down to its middle cell and then gone
+    let mut b = board(5, 5, BLINKER, 1, 2);
+    b.state_types[0].rules[0].probability = 0.0;
+    step(&mut b, 2);
+    assert_eq!(b.population()[1], 0);
+
+    // Cells that never fail never die, so the population only grows
+    let mut b = board(8, 8, GLIDER, 2, 2);
+    b.state_types[1].fail_probability = 0.0;
+    let mut population = b.population()[1];
+    for _ in 0..10 {
+        b.step();
+        assert!(b.population()[1] >= population);
+        population = b.population()[1];
+    }
+}
+
+fn sorted(mut offsets: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+    offsets.sort();
+    offsets
+}
+
+#[test]
+fn neighborhoods_cover_their_shapes() {
+    let ring = |r: i32| {
+        let mut offsets = vec![];
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if (dx, dy) != (0, 0) {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        offsets
+    };
+    let star = |r: i32| {
+        ring(r)
+            .into_iter()
+            .filter(|&(dx, dy)| dx.abs() + dy.abs() <= r)
+            .collect::<Vec<_>>()
+    };
+    let arms = |r: i32| {
+        ring(r)
+            .into_iter()
+            .filter(|&(dx, dy)| dx == 0 || dy == 0)
+            .collect::<Vec<_>>()
+    };
+    let von_neumann_1 = vec![(0, -1), (-1, 0), (1, 0), (0, 1)];
+    assert_eq!(Neighborhood::VonNeumann(1).offsets(), von_neumann_1);
+    assert_eq!(Neighborhood::Cross(1).offsets(), von_neumann_1);
+    assert_eq!(Neighborhood::Moore(1).offsets(), ring(1));
+    assert_eq!(Neighborhood::Moore(2).offsets(), ring(2));
+    assert_eq!(Neighborhood::VonNeumann(2).offsets(), star(2));
+    assert_eq!(Neighborhood::Cross(2).offsets(), arms(2));
+    assert_eq!((ring(2).len(), star(2).len(), arms(2).len()), (24, 12, 8));
+    // The cell itself is never its own neighbor
+    let custom = Neighborhood::Custom(vec![(2, 1), (0, 0), (-1, 0)]);
+    assert_eq!(sorted(custom.offsets()), vec![(-1, 0), (2, 1)]);
+}
+
+#[test]
+fn a_lone_cell_is_born_around_in_each_neighborhood() {
+    // Births next to exactly one live cell, which itself dies, draw the
+    // neighborhood around it: a cell is born where the live one is a neighbor
+    for neighborhood in [
+        Neighborhood::Moore(1),
+        Neighborhood::VonNeumann(2),
+        Neighborhood::Cross(2),
+        Neighborhood::Custom(vec![(1, 0), (0, 2), (-2, -1)]),
+    ] {
+        let mut board = Board::new(9, 9, None);
+        board.neighborhood = neighborhood.clone();
+        board.boundary_x = Boundary::Dead;
+        board.boundary_y = Boundary::Dead;
+        board.state_types[0].rules = vec![Rule::new(1, Condition::count(1, Predicate::Equal(1)))];
+        board.state_types[1].rules = vec![];
+        board.grid.set(4, 4, 1);
+        board.step();
+
+        let mut born = vec![];
+        for y in 0..9 {
+            for x in 0..9 {
+                if board.grid.get(x, y) == 1 {
+                    born.push((4 - x as i32, 4 - y as i32));
+                }
+            }
+        }
+        assert_eq!(
+            sorted(born),
+            sorted(neighborhood.offsets()),
+            "{:?}",
+            neighborhood
+        );
+    }
+}
+
+#[test]
+fn predicates_hold_up_to_their_bounds() {
+    let cases = [
+        (Predicate::Equal(3), vec![3], vec![2, 4]),
+        (Predicate::NotEqual(3), vec![0, 2, 4], vec![3]),
+        (Predicate::Less(3), vec![0, 2], vec![3, 4]),
+        (Predicate::LessEqual(3), vec![0, 3], vec![4]),
+        (Predicate::Greater(3), vec![4, 8], vec![2, 3]),
+        (Predicate::GreaterEqual(3), vec![3, 8], vec![0, 2]),
+        (Predicate::InRange(2, 4), vec![2, 3, 4], vec![1, 5]),
+        (Predicate::InRange(5, 5), vec![5], vec![4, 6]),
+        (Predicate::InRange(5, 4), vec![], vec![4, 5]),
+        (Predicate::InSet(vec![1, 3]), vec![1, 3], vec![0, 2, 4]),
+        (Predicate::InSet(vec![]), vec![], vec![0, 1]),
+    ];
+    for (predicate, holds, fails) in cases {
+        for count in holds {
+            assert!(
+                predicate.test(count),
+                "{} should hold for {}",
+                predicate,
+                count
+            );
+        }
+        for count in fails {
+            assert!(
+                !predicate.test(count),
+                "{} should fail for {}",
+                predicate,
+                count
+            );
+        }
+    }
+}
+
+#[test]
+fn conditions_combine() {
+    let dead = |p| Condition::count(0, p);
+    let alive = |p| Condition::count(1, p);
+    // Life's birth, written the long way: exactly 3 alive and not 4 or more
+    let birth = Condition::And(vec![
+        alive(Predicate::GreaterEqual(3)),
+        Condition::Not(Box::new(alive(Predicate::Greater(3)))),
+    ]);
+    // Survival on 2 or 3, or anywhere with at most 1 dead neighbor
+    let survival = Condition::Or(vec![
+        Condition::And(vec![
+            alive(Predicate::InRange(2, 3)),
+            Condition::Not(Box::new(Condition::Or(vec![]))),
+        ]),
+        dead(Predicate::LessEqual(1)),
+    ]);
+    for alive in 0..=8u32 {
+        let counts = [8 - alive, alive];
+        assert_eq!(birth.test(&counts), alive == 3, "birth with {}", alive);
+        assert_eq!(
+            survival.test(&counts),
+            alive == 2 || alive == 3 || alive >= 7,
+            "survival with {}",
+            alive
+        );
+    }
+    // An empty And always holds and an empty Or never does
+    assert!(Condition::And(vec![]).test(&[0, 0]));
+    assert!(!Condition::Or(vec![]).test(&[0, 0]));
+    assert_eq!(survival.count_states(), vec![1, 0]);
+}
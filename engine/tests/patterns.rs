@@ -0,0 +1,95 @@
+use ca_engine::life::*;
+use ca_engine::patterns::{self, Category, LIBRARY};
+use ca_engine::presets::{self, PRESETS};
+use ca_engine::rulestring;
+
+// The rules an entry is tagged with, as a rulestring or a preset's name
+fn rules(tag: &str) -> (Vec<State>, Neighborhood) {
+    if let Ok((states, neighborhood)) = rulestring::parse(tag) {
+        assert_eq!(rulestring::format(&states, &neighborhood).unwrap(), tag);
+        return (states, neighborhood);
+    }
+    let preset = PRESETS.iter().find(|preset| preset.name == tag).unwrap();
+    let board = preset.board(1, 1);
+    assert_eq!(presets::matching(&board).map(|p| p.name), Some(tag));
+    (board.state_types, board.neighborhood)
+}
+
+// A board with dead edges and room for the pattern to run for generations
+fn board(rule: &str, cells: &Grid, margin: usize) -> Board {
+    let (states, neighborhood) = rules(rule);
+    let (w, h) = (cells.width() + 2 * margin, cells.height() + 2 * margin);
+    let mut board = Board::new(w, h, Some(states));
+    board.neighborhood = neighborhood;
+    board.boundary_x = Boundary::Dead;
+    board.boundary_y = Boundary::Dead;
+    board.grid.paste(cells, margin as i32, margin as i32);
+    board
+}
+
+fn shape(board: &Board) -> Option<Grid> {
+    let (x, y, w, h) = board.grid.bounding_box()?;
+    Some(board.grid.region(x, y, w, h))
+}
+
+#[test]
+fn library_patterns_behave_as_labelled() {
+    for entry in LIBRARY {
+        for &rule in entry.rules {
+            let cells = entry.cells();
+            let mut b = board(rule, &cells, entry.period + 4);
+            let start = (b.grid.bounding_box(), shape(&b));
+            let mut populations = vec![];
+            for generation in 1..=4 * entry.period {
+                b.step();
+                populations.push(b.population()[1]);
+                if entry.category == Category::Gun {
+                    continue;
+                }
+                let now = (b.grid.bounding_box(), shape(&b));
+                let repeats = match entry.category {
+                    Category::Spaceship => now.1 == start.1 && now.0 != start.0,
+                    _ => now == start,
+                };
+                assert_eq!(
+                    repeats,
+                    generation % entry.period == 0,
+                    "{} under {} at generation {}",
+                    entry.name,
+                    rule,
+                    generation
+                );
+            }
+            if entry.category == Category::Gun {
+                let p = entry.period;
+                assert!(
+                    populations[4 * p - 1] > populations[2 * p - 1]
+                        && populations[2 * p - 1] > populations[p - 1],
+                    "{} under {} should keep firing",
+                    entry.name,
+                    rule
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn patterns_are_listed_by_rule() {
+    let life = patterns::for_rule("B3/S23").count();
+    let highlife = patterns::for_rule("B36/S23").count();
+    assert!(highlife > 0 && highlife < life);
+    assert_eq!(patterns::for_rule("Forest Fire").count(), 0);
+    for rule in [
+        "B3678/S34678",
+        "B2/S/C3",
+        "B2/S345/C4",
+        "R5,C2,M0,S33..57,B34..45,NM",
+        "B2/S",
+        "Wireworld",
+        "Rock Paper Scissors",
+    ] {
+        assert!(patterns::for_rule(rule).count() > 0, "{}", rule);
+    }
+    assert!(patterns::for_rule("B2/S/C3").all(|entry| entry.name != "Glider"));
+}
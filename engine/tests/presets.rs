@@ -0,0 +1,38 @@
+use ca_engine::presets::PRESETS;
+
+#[test]
+fn presets_start_with_something_going_on() {
+    for preset in PRESETS {
+        let mut board = preset.board(64, 48);
+        let states = board.state_types.len();
+        assert!(
+            board.grid.cells().iter().all(|&c| (c as usize) < states),
+            "{}",
+            preset.name
+        );
+        assert!(board.grid.bounding_box().is_some(), "{}", preset.name);
+        for _ in 0..20 {
+            board.step();
+        }
+        let before = board.grid.clone();
+        board.step();
+        assert_ne!(before, board.grid, "{} has stopped", preset.name);
+    }
+}
+
+#[test]
+fn wireworld_clock_keeps_ticking() {
+    let preset = PRESETS.iter().find(|p| p.name == "Wireworld").unwrap();
+    let mut board = preset.board(40, 9);
+    for _ in 0..200 {
+        board.step();
+    }
+    // One electron in the loop, and pulses spaced a lap apart along the wire
+    let heads = board.population()[1];
+    assert!(heads >= 2, "{} electron heads", heads);
+    let start = board.grid.clone();
+    for _ in 0..18 {
+        board.step();
+    }
+    assert_eq!(board.grid, start);
+}
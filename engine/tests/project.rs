@@ -0,0 +1,85 @@
+use ca_engine::life::*;
+use ca_engine::project::{Project, VERSION};
+
+// A saved Game of Life project with a glider on it
+fn project() -> Project {
+    let mut board = Board::new(8, 6, None);
+    board.grid = Grid::from_rows(&[vec![0, 1, 0], vec![0, 0, 1], vec![1, 1, 1]]).resized(
+        8,
+        6,
+        Anchor::TopLeft,
+    );
+    Project::new(&board, 1000)
+}
+
+#[test]
+fn projects_survive_a_round_trip() {
+    let saved = project();
+    assert_eq!(Project::from_json(&saved.to_json()).unwrap(), saved);
+    assert_eq!(Project::from_json(&saved.to_compact_json()).unwrap(), saved);
+    assert!(!saved.to_compact_json().contains('\n'));
+    assert_eq!(Project::from_link(&saved.to_link()).unwrap(), saved);
+    assert_eq!(saved.clone().into_board().grid, saved.into_board().grid);
+}
+
+#[test]
+fn version_1_projects_open_with_the_defaults() {
+    let mut json = serde_json::to_value(project()).unwrap();
+    json["version"] = 1.into();
+    json.as_object_mut().unwrap().remove("seed");
+    for state in json["states"].as_array_mut().unwrap() {
+        let state = state.as_object_mut().unwrap();
+        state.remove("fail_probability");
+        for rule in state["rules"].as_array_mut().unwrap() {
+            rule.as_object_mut().unwrap().remove("probability");
+        }
+    }
+    let opened = Project::from_json(&json.to_string()).unwrap();
+    assert_eq!(opened, project());
+    assert_eq!(opened.version, VERSION);
+
+    json["version"] = (VERSION + 1).into();
+    assert!(Project::from_json(&json.to_string()).is_err());
+}
+
+#[test]
+fn grids_are_held_to_the_project_size() {
+    let mut crafted = project();
+    crafted.grid = String::from("x = 4000000000, y = 4000000000\no!");
+    assert!(crafted.validate().is_err());
+    crafted.grid = String::from("x = 8, y = 6\n999999999o!");
+    assert!(crafted.validate().is_err());
+    crafted.grid = String::from("x = 9, y = 6\no!");
+    assert!(crafted.validate().is_err());
+}
+
+#[test]
+fn neighborhoods_are_bounded() {
+    let mut crafted = project();
+    for neighborhood in [
+        Neighborhood::Moore(100000),
+        Neighborhood::Custom(vec![(1, 0); MAX_OFFSETS + 1]),
+        Neighborhood::Custom(vec![(1, 0), (0, 0)]),
+        Neighborhood::Custom(vec![(1, 0), (0, -4000)]),
+    ] {
+        crafted.neighborhood = neighborhood;
+        assert!(crafted.validate().is_err());
+    }
+    crafted.neighborhood = Neighborhood::Custom(vec![(1, 0), (0, -(MAX_RADIUS as i32))]);
+    assert!(crafted.validate().is_ok());
+}
+
+#[test]
+fn border_states_must_exist() {
+    let mut crafted = project();
+    crafted.boundary_y = Boundary::State(1);
+    assert!(crafted.validate().is_ok());
+    crafted.boundary_y = Boundary::State(2);
+    assert!(crafted.validate().is_err());
+
+    // and boards drop them along with the states
+    crafted.boundary_y = Boundary::State(1);
+    let mut board = crafted.into_board();
+    board.set_state_types(board.state_types[..1].to_vec());
+    assert_eq!(board.boundary_y, Boundary::State(0));
+}
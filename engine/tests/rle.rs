@@ -0,0 +1,31 @@
+use ca_engine::life::Grid;
+use ca_engine::rle;
+
+#[test]
+fn patterns_survive_a_round_trip() {
+    let glider = Grid::from_rows(&[vec![0, 1, 0], vec![0, 0, 1], vec![1, 1, 1]]);
+    let many = Grid::from_rows(&[vec![0, 2, 30], vec![0, 0, 0], vec![5, 0, 1]]);
+    for (cells, rule) in [
+        (&glider, "B3/S23"),
+        (&many, "B2/S/C31"),
+        (&glider, "R5,C2,M0,S33..57,B34..45,NM"),
+    ] {
+        let pattern = rle::parse(&rle::write(cells, Some(rule))).unwrap();
+        assert_eq!(&pattern.cells, cells);
+        assert_eq!(pattern.rule.as_deref(), Some(rule));
+    }
+}
+
+#[test]
+fn headers_and_runs_are_bounded() {
+    assert!(rle::parse("x = 4000000000, y = 4000000000\no!").is_err());
+    assert!(rle::parse("x = 3, y = 3\n999999999o!").is_err());
+    assert!(rle::parse("x = 3, y = 3\n999999999$o!").is_err());
+    assert!(rle::parse_within("x = 3, y = 3\n4o!", 3, 3).is_err());
+    assert!(rle::parse_within("x = 3, y = 3\n3o$3o$3o!", 3, 3).is_ok());
+    assert!(rle::parse("y = 3\no!").is_err());
+    assert!(rle::parse("x = 3, y = 3, rule\no!").is_err());
+    assert!(rle::parse("x = 3, y = 3\nop!").is_err());
+    assert!(rle::parse("x = 3, y = 3\no3!").is_err());
+    assert!(rle::parse("x = 3, y = 3\no3").is_err());
+}
@@ -0,0 +1,73 @@
+use ca_engine::life::*;
+use ca_engine::rulestring;
+
+fn round_trip(rule: &str) -> String {
+    let (states, neighborhood) = rulestring::parse(rule).unwrap();
+    rulestring::format(&states, &neighborhood).unwrap()
+}
+
+#[test]
+fn rules_survive_a_round_trip() {
+    for (rule, written) in [
+        ("B3/S23", "B3/S23"),
+        ("b36/s23", "B36/S23"),
+        ("23/3", "B3/S23"),
+        ("B2/S/C3", "B2/S/C3"),
+        ("345/2/4", "B2/S345/C4"),
+        ("B2/S/C256", "B2/S/C256"),
+        ("B13/S012V", "B13/S012V"),
+        ("R5,C2,M0,S33..57,B34..45,NM", "R5,C2,M0,S33..57,B34..45,NM"),
+        ("R2,C3,M1,S3..5,B3..3,NN", "R2,C3,M0,S2..4,B3..3,NN"),
+        ("R2,C2,M0,S2-3,5,B3..3,NM", "R2,C2,M0,S2-3,5,B3..3,NM"),
+        ("R2,C2,M1,S3-4,6,B3,NM", "R2,C2,M0,S2-3,5,B3..3,NM"),
+        ("R2,C2,M0,S2-3,B3,24-40,NM", "R2,C2,M0,S2..3,B3,24,NM"),
+    ] {
+        assert_eq!(round_trip(rule), written, "{}", rule);
+    }
+}
+
+#[test]
+fn bad_rules_are_rejected() {
+    for rule in [
+        "",
+        "/",
+        "//",
+        "B3",
+        "B9/S23",
+        "Bx/S23",
+        "B3/S23/C1",
+        "B3/S23/C4000000000",
+        "B3/S23/C257",
+        "R0,C2,M0,S1..2,B1..2,NM",
+        "R100000,C2,M0,S1..2,B1..2,NM",
+        "R2,C4000000000,M0,S1..2,B1..2,NM",
+        "R2,C2,M0,S1..2,NM",
+        "R2,C2,M0,S1..2,B1..2,NX",
+        "R2,C2,M0,S1,x,B1..2,NM",
+        "R2,C2,M0,S1..2,B,NM",
+    ] {
+        assert!(rulestring::parse(rule).is_err(), "{}", rule);
+    }
+}
+
+#[test]
+fn only_some_rules_have_a_rulestring() {
+    let (states, _) = rulestring::parse("B3/S23").unwrap();
+    assert!(rulestring::format(&states, &Neighborhood::Custom(vec![(1, 0)])).is_err());
+
+    // Larger than Life counts that aren't a single range are written as a list
+    let (mut states, neighborhood) = rulestring::parse("R2,C2,M0,S3..5,B3..3,NM").unwrap();
+    states[0].rules[0].condition = Condition::count(1, Predicate::InSet(vec![3, 5]));
+    let rule = rulestring::format(&states, &neighborhood).unwrap();
+    assert_eq!(rule, "R2,C2,M0,S3..5,B3,5,NM");
+    assert_eq!(rulestring::parse(&rule).unwrap(), (states, neighborhood));
+    // but need at least one birth count
+    let (mut states, neighborhood) = rulestring::parse("R2,C2,M0,S3..5,B3..3,NM").unwrap();
+    states[0].rules.clear();
+    assert!(rulestring::format(&states, &neighborhood).is_err());
+
+    // and rules left to chance have none
+    let mut life = Board::new(1, 1, None);
+    life.state_types[0].rules[0].probability = 0.5;
+    assert!(rulestring::format(&life.state_types, &life.neighborhood).is_err());
+}
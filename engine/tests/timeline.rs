@@ -0,0 +1,66 @@
+mod common;
+
+use ca_engine::random::Random;
+use ca_engine::timeline::Timeline;
+use common::random_board;
+
+#[test]
+fn seeking_rebuilds_every_generation() {
+    let mut board = random_board("B3/S23", 40, 30, &mut Random::new(3));
+    let mut timeline = Timeline::new(board.grid.clone(), 1000);
+    let mut grids = vec![board.grid.clone()];
+    for _ in 0..100 {
+        let before = board.grid.clone();
+        board.step();
+        timeline.record(&before, &board.grid);
+        grids.push(board.grid.clone());
+    }
+    assert_eq!((timeline.first(), timeline.last()), (0, 100));
+    for generation in [0, 31, 32, 33, 64, 99, 100, 7, 50] {
+        assert_eq!(timeline.seek(generation), Some(&grids[generation]));
+        assert_eq!(timeline.position(), generation);
+    }
+    assert_eq!(timeline.seek(101), None);
+}
+
+#[test]
+fn only_the_last_capacity_generations_are_kept() {
+    let mut board = random_board("B36/S23", 20, 20, &mut Random::new(5));
+    let mut timeline = Timeline::new(board.grid.clone(), 5);
+    let mut grids = vec![board.grid.clone()];
+    for _ in 0..80 {
+        let before = board.grid.clone();
+        board.step();
+        timeline.record(&before, &board.grid);
+        grids.push(board.grid.clone());
+    }
+    assert_eq!((timeline.first(), timeline.last()), (76, 80));
+    assert_eq!(timeline.seek(75), None);
+    for (generation, grid) in grids.iter().enumerate().skip(76) {
+        assert_eq!(timeline.seek(generation), Some(grid));
+    }
+}
+
+#[test]
+fn stepping_from_the_past_forgets_the_future() {
+    let mut board = random_board("B3/S23", 16, 16, &mut Random::new(8));
+    let mut timeline = Timeline::new(board.grid.clone(), 100);
+    for _ in 0..40 {
+        let before = board.grid.clone();
+        board.step();
+        timeline.record(&before, &board.grid);
+    }
+    board.grid = timeline.seek(10).unwrap().clone();
+    let before = board.grid.clone();
+    board.grid.set(0, 0, 1 - board.grid.get(0, 0));
+    timeline.record(&before, &board.grid);
+    assert_eq!((timeline.first(), timeline.last()), (0, 11));
+    assert_eq!(timeline.seek(11), Some(&board.grid));
+    assert_eq!(timeline.seek(12), None);
+
+    // A grid the timeline hasn't seen starts it over
+    let edited = random_board("B3/S23", 16, 16, &mut Random::new(9)).grid;
+    timeline.record(&edited, &board.grid);
+    assert_eq!((timeline.first(), timeline.last()), (0, 1));
+    assert_eq!(timeline.seek(0), Some(&edited));
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use ca_engine::life::*;
+use ca_engine::random::Random;
+use ca_engine::universe::Universe;
+use ca_engine::{rle, rulestring};
+use common::random_board;
+
+const GLIDER: &str = "x = 3, y = 3\nbob$2bo$3o!";
+
+#[test]
+fn glider_flies_off_without_wrapping() {
+    let mut board = Board::new(8, 8, None);
+    board.grid.paste(&rle::parse(GLIDER).unwrap().cells, 0, 0);
+    let mut universe = Universe::from_board(&board);
+    for _ in 0..400 {
+        universe.step().unwrap();
+    }
+    // A glider moves one cell diagonally every 4 generations
+    assert_eq!(universe.bounding_box(), Some((100, 100, 3, 3)));
+    assert_eq!(
+        universe.region(100, 100, 3, 3),
+        rle::parse(GLIDER).unwrap().cells
+    );
+    assert!(universe.chunks() <= 4);
+}
+
+#[test]
+fn matches_a_board_big_enough_to_hold_the_pattern() {
+    // A soup in the middle of a large board doesn't reach the edges in time
+    let (states, neighborhood) = rulestring::parse("B36/S23").unwrap();
+    let mut board = Board::new(160, 160, Some(states));
+    board.neighborhood = neighborhood;
+    board.boundary_x = Boundary::Dead;
+    board.boundary_y = Boundary::Dead;
+    let soup = random_board("B36/S23", 40, 40, &mut Random::new(0x853C49E6748FEA9B));
+    board.grid.paste(&soup.grid, 60, 60);
+    let mut universe = Universe::from_board(&board);
+    for _ in 0..50 {
+        board.step();
+        universe.step().unwrap();
+    }
+    assert!(board.grid == universe.region(0, 0, 160, 160));
+    let (x, y, _, _) = universe.bounding_box().unwrap();
+    assert!(x > 0 && y > 0);
+}
+
+#[test]
+fn rejects_rules_that_fill_empty_space() {
+    let (states, neighborhood) = rulestring::parse("B0/S8").unwrap();
+    let mut universe = Universe::new(states, neighborhood);
+    universe.set(3, 4, 1);
+    assert!(universe.step().is_err());
+}
+
+#[test]
+fn empty_chunks_are_dropped() {
+    let board = Board::new(4, 4, None);
+    let mut universe = Universe::from_board(&board);
+    universe.set(-40, 70, 1);
+    assert_eq!(universe.chunks(), 1);
+    universe.step().unwrap();
+    assert_eq!(universe.chunks(), 0);
+    assert_eq!(universe.bounding_box(), None);
+}
+
+#[test]
+fn chance_wakes_still_chunks() {
+    // A block normally never changes, but here its cells only survive most
+    // of the time
+    let run = |seed| {
+        let mut board = Board::new(4, 4, None);
+        board
+            .grid
+            .paste(&rle::parse("x = 2, y = 2\n2o$2o!").unwrap().cells, 1, 1);
+        for rule in &mut board.state_types[1].rules {
+            rule.probability = 0.9;
+        }
+        board.reseed(seed);
+        let mut universe = Universe::from_board(&board);
+        for _ in 0..20 {
+            universe.step().unwrap();
+        }
+        universe.region(-8, -8, 20, 20)
+    };
+    let block = run(5);
+    assert_ne!(
+        block.region(9, 9, 2, 2),
+        Grid::from_rows(&[vec![1, 1], vec![1, 1]])
+    );
+    assert_eq!(run(5), block);
+}
@@ -0,0 +1,41 @@
+use ca_engine::viewport::*;
+
+#[test]
+fn zooming_keeps_the_cell_under_the_mouse() {
+    let mut viewport = Viewport::new(32.0);
+    viewport.pan(-40.0, 12.0);
+    let before = viewport.cell(500.0, 300.0);
+    for factor in [2.0, 0.5, 1.25, 0.8, 0.1] {
+        viewport.zoom(factor, 500.0, 300.0);
+        assert_eq!(viewport.cell(500.0, 300.0), before);
+    }
+    viewport.zoom(1000.0, 0.0, 0.0);
+    assert_eq!(viewport.cell_size, MAX_CELL_SIZE);
+}
+
+#[test]
+fn fit_centers_the_cells() {
+    let mut viewport = Viewport::new(32.0);
+    viewport.fit((10, 20, 4, 2), 1024.0, 512.0);
+    assert_eq!(viewport.cell_size, 128.0);
+    assert_eq!(viewport.cell(512.0, 256.0), (12, 21));
+    assert_eq!(viewport.cell(256.0, 128.0), (10, 20));
+    assert_eq!(viewport.cell(767.0, 383.0), (13, 21));
+}
+
+#[test]
+fn only_cells_on_the_canvas_are_visible() {
+    let mut viewport = Viewport::new(32.0);
+    assert_eq!(viewport.visible((1024.0, 512.0), (32, 16)), (0..32, 0..16));
+    assert_eq!(
+        viewport.visible((1024.0, 512.0), (100, 100)),
+        (0..32, 0..16)
+    );
+    viewport.pan(-48.0, 16.0);
+    assert_eq!(
+        viewport.visible((1024.0, 512.0), (100, 100)),
+        (1..34, 0..16)
+    );
+    viewport.pan(5000.0, 0.0);
+    assert_eq!(viewport.visible((1024.0, 512.0), (100, 100)), (0..0, 0..16));
+}
@@ -1,125 +1,508 @@
+use crate::brush::{self, Tool};
+use crate::files::{self, Files};
+use crate::history::{self, History};
+use crate::library::{self, Library};
 use crate::life::*;
+use crate::project::Project;
+use crate::selection::{self, Rect, Selection};
 use crate::settings::Settings;
 use crate::states::States;
+use crate::viewport::Viewport;
 use leptos::html::Canvas;
 use leptos::*;
 use std::f64;
 pub use std::rc::Rc;
+use std::time::Duration;
 use wasm_bindgen::JsCast;
 #[component]
-pub fn Canvas() -> impl IntoView {
+pub fn Canvas(active: Signal<bool>) -> impl IntoView {
     let (width, _) = create_signal(1024);
     let (height, _) = create_signal(512);
-    let (cell_size, set_cell_size) = create_signal(32 as i32);
-    let w = move || (width() / cell_size()) as usize;
-    let h = move || (height() / cell_size()) as usize;
-    let (board, set_board) = create_signal(Board::new(w(), h(), None));
+    let (viewport, set_viewport) = create_signal(Viewport::new(DEFAULT_CELL_SIZE));
+    let (w, h) = DEFAULT_SIZE;
+    // Open a shared link if there is one, else pick up where the last visit
+    // left off, falling back to Game of Life. Anything that can't be opened
+    // is reported rather than quietly replaced.
+    let (load_error, set_load_error) = create_signal(String::new());
+    let open = move |project: Option<Result<Project, String>>, what: &str| match project? {
+        Ok(project) => Some(project),
+        Err(e) => {
+            set_load_error(format!("couldn't open the {}: {}", what, e));
+            None
+        }
+    };
+    let session = open(files::take_link(), "shared link")
+        .or_else(|| open(files::load_session(), "last session"))
+        .map(|project| (project.delay, project.into_board()));
+    let (board, set_board) = match &session {
+        Some((_, board)) => create_signal(board.clone()),
+        None => create_signal(Board::new(w, h, None)),
+    };
     let (curr_state, set_curr_state) = create_signal(1usize);
+    let (delay, set_delay) = create_signal(session.map_or(1000u64, |(delay, _)| delay));
+    // Bumped whenever the state types are replaced wholesale, so the state
+    // editor is rebuilt from the board
+    let (states_revision, set_states_revision) = create_signal(0usize);
     let canvas_ref: NodeRef<html::Canvas> = create_node_ref();
+    let history = store_value(History::new(history::DEFAULT_BUDGET));
 
     canvas_ref.on_load(move |canvas_ref| {
         canvas_ref.on_mount(move |x| {
-            render_grid(x, width(), height(), cell_size());
+            render_board(x, width(), height(), &viewport(), &board());
         });
     });
 
-    let click_function = move |mouse: ev::MouseEvent| {
-        let x_index = index(mouse.page_x(), cell_size(), width);
-        let y_index = index(mouse.page_y(), cell_size(), height);
-        let state = board().state_types[curr_state()].clone();
-        let ctx = canvas_ref
-            .get()
-            .unwrap()
-            .get_context("2d")
-            .ok()
-            .flatten()
-            .expect("canvas to have context")
-            .unchecked_into::<web_sys::CanvasRenderingContext2d>();
-        set_board.update(|b| b.grid[y_index][x_index] = state.index);
-        ctx.set_fill_style(&wasm_bindgen::JsValue::from_str(&state.color));
+    // Autosave changes to the board or the delay. A change waits out
+    // AUTOSAVE_DELAY and later ones ride along, so a running board is saved
+    // once in a while rather than every generation.
+    let autosave = store_value(None::<TimeoutHandle>);
+    create_effect(move |_| {
+        board.track();
+        delay.track();
+        if autosave.with_value(|pending| pending.is_some()) {
+            return;
+        }
+        let save = move || {
+            autosave.set_value(None);
+            let project = board.with_untracked(|b| Project::new(b, delay.get_untracked()));
+            files::save_session(&project);
+        };
+        autosave.set_value(set_timeout_with_handle(save, AUTOSAVE_DELAY).ok());
+    });
+
+    let redraw = move || {
         render_board(
             canvas_ref.get().unwrap(),
             width(),
             height(),
-            cell_size(),
+            &viewport(),
             &board(),
-        );
+        )
+    };
+
+    // Steps back or forward through the edit history. Running the simulation
+    // isn't an edit, so undo goes back to the last edit made before it.
+    let travel = move |redo: bool| {
+        let mut b = board.get_untracked();
+        let moved = history
+            .try_update_value(|h| if redo { h.redo(&mut b) } else { h.undo(&mut b) })
+            .unwrap_or(false);
+        if !moved {
+            return;
+        }
+        let states_changed = board.with_untracked(|old| old.state_types != b.state_types);
+        // Batched, so the state editor is rebuilt before it sees fewer states
+        batch(|| {
+            set_board(b);
+            if states_changed {
+                set_states_revision.update(|r| *r += 1);
+            }
+        });
+        redraw();
+    };
+
+    // Ctrl+Z undoes, Ctrl+Shift+Z or Ctrl+Y redoes. Text boxes keep their own undo.
+    _ = window_event_listener(ev::keydown, move |ev| {
+        if !active() || !(ev.ctrl_key() || ev.meta_key()) {
+            return;
+        }
+        let in_text_box = ev
+            .target()
+            .is_some_and(|t| t.dyn_ref::<web_sys::HtmlInputElement>().is_some());
+        if in_text_box {
+            return;
+        }
+        match ev.key().to_lowercase().as_str() {
+            "z" => travel(ev.shift_key()),
+            "y" => travel(true),
+            _ => return,
+        }
+        ev.prevent_default();
+    });
+
+    let (tool, set_tool) = create_signal(Tool::Freehand);
+    let (brush_size, set_brush_size) = create_signal(1usize);
+    let (eraser, set_eraser) = create_signal(false);
+    let stroke = store_value(None::<Stroke>);
+    let (selection, set_selection) = create_signal(None::<Rect>);
+    let (stamp, set_stamp) = create_signal(None::<Grid>);
+    let overlay_ref: NodeRef<html::Canvas> = create_node_ref();
+    create_effect(move |_| {
+        let (viewport, selection) = (viewport(), selection());
+        if let Some(overlay) = overlay_ref.get() {
+            selection::render_selection(overlay, width(), height(), &viewport, selection);
+        }
+    });
+    let cell_under = move |mouse: &ev::MouseEvent| {
+        viewport
+            .get_untracked()
+            .cell(mouse.offset_x() as f64, mouse.offset_y() as f64)
+    };
+
+    // Freehand strokes build up as the mouse moves, while everything else is
+    // drawn again from the board as it was before the stroke, so shapes follow
+    // the mouse
+    let draw = move |to: (i64, i64)| {
+        let state = match eraser() {
+            true => 0,
+            false => board.with_untracked(|b| b.state_types[curr_state()].index),
+        };
+        let (tool, size) = (tool(), brush_size());
+        stroke.update_value(|stroke| {
+            let Some(stroke) = stroke else { return };
+            let previous = std::mem::replace(&mut stroke.last, to);
+            if tool == Tool::Select {
+                let (w, h) = board.with_untracked(|b| (b.grid.width(), b.grid.height()));
+                return set_selection(select(stroke.start, to, w, h));
+            }
+            set_board.update(|b| match tool {
+                Tool::Freehand => brush::paint(&mut b.grid, &tool.shape(previous, to), size, state),
+                // Follows the mouse until it's let go, centered on it
+                Tool::Stamp => {
+                    b.grid = stroke.before.grid.clone();
+                    stamp.with_untracked(|stamp| {
+                        let states = b.state_types.len();
+                        if let Some(cells) = stamp.as_ref().filter(|c| library::fits(c, states)) {
+                            let x = to.0 - cells.width() as i64 / 2;
+                            let y = to.1 - cells.height() as i64 / 2;
+                            b.grid.paste(cells, x as i32, y as i32);
+                        }
+                    });
+                }
+                Tool::Fill => {
+                    b.grid = stroke.before.grid.clone();
+                    let (x, y) = stroke.start;
+                    let (w, h) = (b.grid.width() as i64, b.grid.height() as i64);
+                    if (0..w).contains(&x) && (0..h).contains(&y) {
+                        brush::flood_fill(&mut b.grid, x as usize, y as usize, state);
+                    }
+                }
+                _ => {
+                    b.grid = stroke.before.grid.clone();
+                    brush::paint(&mut b.grid, &tool.shape(stroke.start, to), size, state);
+                }
+            });
+        });
+        redraw();
+    };
+    let start_stroke = move |mouse: ev::MouseEvent| {
+        let at = cell_under(&mouse);
+        stroke.set_value(Some(Stroke {
+            before: board.get_untracked(),
+            start: at,
+            last: at,
+        }));
+        draw(at);
+    };
+    let move_stroke = move |mouse: ev::MouseEvent| {
+        let at = cell_under(&mouse);
+        if stroke.with_value(|s| s.as_ref().is_some_and(|s| s.last != at)) {
+            draw(at);
+        }
+    };
+    // The whole stroke is one undo step
+    let end_stroke = move || {
+        if let Some(stroke) = stroke.try_update_value(|s| s.take()).flatten() {
+            board.with_untracked(|after| history.update_value(|h| h.record(&stroke.before, after)));
+        }
+    };
+
+    // Dragging with the right or middle button pans, and the wheel zooms
+    // around the mouse
+    let drag = store_value(None::<(i32, i32)>);
+    let pan_function = move |mouse: ev::MouseEvent| {
+        let Some((x, y)) = drag.get_value() else {
+            return;
+        };
+        let (to_x, to_y) = (mouse.offset_x(), mouse.offset_y());
+        set_viewport.update(|v| v.pan((to_x - x) as f64, (to_y - y) as f64));
+        drag.set_value(Some((to_x, to_y)));
+        redraw();
+    };
+    let wheel_function = move |wheel: ev::WheelEvent| {
+        wheel.prevent_default();
+        let factor = match wheel.delta_y() < 0.0 {
+            true => ZOOM,
+            false => 1.0 / ZOOM,
+        };
+        let (x, y) = (wheel.offset_x() as f64, wheel.offset_y() as f64);
+        set_viewport.update(|v| v.zoom(factor, x, y));
+        redraw();
+    };
+
+    // Fits the live cells, or the whole board when there aren't any
+    let fit = move || {
+        let cells = board.with(|b| {
+            b.grid
+                .bounding_box()
+                .unwrap_or((0, 0, b.grid.width(), b.grid.height()))
+        });
+        set_viewport.update(|v| v.fit(cells, width() as f64, height() as f64));
+        redraw();
     };
 
     view! {
-        <div>
+        <div>{load_error}</div>
+        <div class="stack">
             <canvas
-                on:click=move |mouse| { click_function(mouse) }
                 width=move || { width() }
                 height=move || { height() }
                 class="canvas"
                 node_ref=canvas_ref
             ></canvas>
+            // The selection is drawn on its own canvas over the board, so
+            // redrawing the board doesn't wipe it
+            <canvas
+                on:mousedown=move |mouse| {
+                    mouse.prevent_default();
+                    if mouse.button() == 0 {
+                        start_stroke(mouse);
+                    } else {
+                        drag.set_value(Some((mouse.offset_x(), mouse.offset_y())));
+                    }
+                }
+                on:mousemove=move |mouse| {
+                    move_stroke(mouse.clone());
+                    pan_function(mouse);
+                }
+                on:mouseup=move |_| {
+                    end_stroke();
+                    drag.set_value(None);
+                }
+                on:mouseleave=move |_| {
+                    end_stroke();
+                    drag.set_value(None);
+                }
+                on:contextmenu=move |mouse| mouse.prevent_default()
+                on:wheel=wheel_function
+                width=move || { width() }
+                height=move || { height() }
+                class="overlay"
+                node_ref=overlay_ref
+            ></canvas>
 
         </div>
+        <div>
+            "Tool: "
+            <select on:input=move |ev| {
+                if let Some(&(tool, _)) = event_target_value(&ev)
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| TOOLS.get(i))
+                {
+                    set_tool(tool);
+                }
+            }>
+                {TOOLS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(t, name))| {
+                        view! {
+                            <option value=i selected=move || tool() == t>
+                                {name}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
+            </select>
+            " Brush Size: "
+            <input
+                type="number"
+                min="1"
+                max=MAX_BRUSH_SIZE
+                value=brush_size
+                on:input=move |ev| {
+                    if let Ok(size) = event_target_value(&ev).parse::<usize>() {
+                        set_brush_size(size.clamp(1, MAX_BRUSH_SIZE));
+                    }
+                }
+            />
+            " "
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=eraser
+                    on:change=move |ev| set_eraser(event_target_checked(&ev))
+                />
+                " Eraser"
+            </label>
+        </div>
+        <div>
+            <button on:click=move |_| travel(false)>"Undo"</button>
+            <button on:click=move |_| travel(true)>"Redo"</button>
+            " "
+            <button on:click=move |_| fit()>"Fit to Pattern"</button>
+            " Undo Memory (KB): "
+            <input
+                type="number"
+                min="0"
+                value=history.with_value(|h| h.budget()) / 1024
+                on:change=move |ev| {
+                    if let Ok(kb) = event_target_value(&ev).parse::<usize>() {
+                        history.update_value(|h| h.set_budget(kb * 1024));
+                    }
+                }
+            />
+        </div>
+        <Selection
+            canvas_ref=canvas_ref
+            width=width
+            height=height
+            viewport=viewport
+            r_board=board
+            w_board=set_board
+            render_board=render_board
+            selection=selection
+            set_selection=set_selection
+            history=history
+            active=active
+        />
+        <Library
+            r_board=board
+            render_board=render_board
+            stamp=stamp
+            set_stamp=set_stamp
+            set_tool=set_tool
+        />
         <Settings
             canvas_ref=canvas_ref
             width=width
             height=height
-            r_cell_size=cell_size
-            w_cell_size=set_cell_size
+            viewport=viewport
+            set_viewport=set_viewport
             r_board=board
             w_board=set_board
-            render_grid=render_grid
             render_board=render_board
+            set_states_revision=set_states_revision
+            delay=delay
+            set_delay=set_delay
+            history=history
+            active=active
         />
-        <States
+        <Files
             canvas_ref=canvas_ref
             width=width
             height=height
-            r_cell_size=cell_size
+            viewport=viewport
             r_board=board
             w_board=set_board
-            render_grid=render_grid
             render_board=render_board
-            set_state=set_curr_state
+            set_states_revision=set_states_revision
+            delay=delay
+            set_delay=set_delay
+            history=history
         />
+        {move || {
+            states_revision();
+            untrack(|| {
+                view! {
+                    <States
+                        canvas_ref=canvas_ref
+                        width=width
+                        height=height
+                        viewport=viewport
+                        r_board=board
+                        w_board=set_board
+                        render_board=render_board
+                        set_state=set_curr_state
+                        history=history
+                    />
+                }
+            })
+        }}
     }
 }
 
-fn index(n: i32, x: i32, limit: ReadSignal<i32>) -> usize {
-    if n != limit() {
-        ((n - n % x) / x) as usize
-    } else {
-        ((n / x) - 1) as usize
-    }
+// Applies an edit to the board and records it as one undo step
+pub fn edit_board(
+    r_board: ReadSignal<Board>,
+    w_board: WriteSignal<Board>,
+    history: StoredValue<History>,
+    f: impl FnOnce(&mut Board),
+) {
+    let before = r_board.get_untracked();
+    w_board.update(f);
+    r_board.with_untracked(|after| history.update_value(|h| h.record(&before, after)));
 }
 
-fn render_grid(canvas_ref: HtmlElement<Canvas>, width: i32, height: i32, cell_size: i32) {
-    let ctx = canvas_ref
-        .get_context("2d")
-        .ok()
-        .flatten()
-        .expect("canvas to have context")
-        .unchecked_into::<web_sys::CanvasRenderingContext2d>();
+// A stroke being painted: the board before it, where it started and where the
+// mouse was last
+struct Stroke {
+    before: Board,
+    start: (i64, i64),
+    last: (i64, i64),
+}
 
-    for i in (0..=width).step_by(cell_size as usize) {
-        ctx.set_stroke_style(&wasm_bindgen::JsValue::from_str("#FFFFFF"));
-        ctx.begin_path();
-        ctx.move_to(i as f64, 0.);
-        ctx.line_to(i as f64, height as f64);
-        ctx.stroke();
+// The rectangle between two cells, pulled in onto a width x height board
+fn select(from: (i64, i64), to: (i64, i64), width: usize, height: usize) -> Option<Rect> {
+    if width == 0 || height == 0 {
+        return None;
     }
+    let clamp = |(x, y): (i64, i64)| {
+        (
+            x.clamp(0, width as i64 - 1) as usize,
+            y.clamp(0, height as i64 - 1) as usize,
+        )
+    };
+    let (from, to) = (clamp(from), clamp(to));
+    let (x, y) = (from.0.min(to.0), from.1.min(to.1));
+    Some((x, y, from.0.max(to.0) - x + 1, from.1.max(to.1) - y + 1))
+}
+
+const TOOLS: [(Tool, &str); 9] = [
+    (Tool::Freehand, "Pencil"),
+    (Tool::Line, "Line"),
+    (Tool::Rectangle, "Rectangle"),
+    (Tool::FilledRectangle, "Filled Rectangle"),
+    (Tool::Circle, "Circle"),
+    (Tool::FilledCircle, "Filled Circle"),
+    (Tool::Fill, "Flood Fill"),
+    (Tool::Select, "Select"),
+    (Tool::Stamp, "Stamp"),
+];
+const MAX_BRUSH_SIZE: usize = 64;
 
-    for i in (0..=height).step_by(cell_size as usize) {
-        ctx.set_stroke_style(&wasm_bindgen::JsValue::from_str("#FFFFFF"));
-        ctx.begin_path();
-        ctx.move_to(0., i as f64);
-        ctx.line_to(width as f64, i as f64);
-        ctx.stroke();
+// The board a first visit starts with, and how big its cells are drawn
+const DEFAULT_SIZE: (usize, usize) = (32, 16);
+const DEFAULT_CELL_SIZE: f64 = 32.0;
+// How much one notch of the mouse wheel zooms by
+const ZOOM: f64 = 1.25;
+// Grid lines are left out when cells are smaller than this, as they'd hide them
+const MIN_GRID_CELL_SIZE: f64 = 4.0;
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(1);
+
+fn render_grid(
+    ctx: &web_sys::CanvasRenderingContext2d,
+    viewport: &Viewport,
+    board: (usize, usize),
+) {
+    if viewport.cell_size < MIN_GRID_CELL_SIZE {
+        return;
+    }
+    let (right, bottom) = (
+        viewport.x + board.0 as f64 * viewport.cell_size,
+        viewport.y + board.1 as f64 * viewport.cell_size,
+    );
+    ctx.set_stroke_style(&wasm_bindgen::JsValue::from_str("#FFFFFF"));
+    ctx.begin_path();
+    for i in 0..=board.0 {
+        let x = viewport.x + i as f64 * viewport.cell_size;
+        ctx.move_to(x, viewport.y);
+        ctx.line_to(x, bottom);
+    }
+    for i in 0..=board.1 {
+        let y = viewport.y + i as f64 * viewport.cell_size;
+        ctx.move_to(viewport.x, y);
+        ctx.line_to(right, y);
     }
+    ctx.stroke();
 }
 
 fn render_board(
     canvas_ref: HtmlElement<Canvas>,
     width: i32,
     height: i32,
-    cell_size: i32,
+    viewport: &Viewport,
     board: &Board,
 ) {
     let ctx = canvas_ref
@@ -128,27 +511,28 @@ fn render_board(
         .flatten()
         .expect("canvas to have context")
         .unchecked_into::<web_sys::CanvasRenderingContext2d>();
-    let (grid, state_types) = (board.grid.clone(), board.state_types.clone());
-    let grid_height = grid.len();
-    let grid_width = grid[0].len();
+    let (grid, state_types) = (&board.grid, &board.state_types);
+    let size = (grid.width(), grid.height());
+    ctx.clear_rect(0.0, 0.0, width as f64, height as f64);
 
-    for i in 0..grid_height {
-        for j in 0..grid_width {
+    let (columns, rows) = viewport.visible((width as f64, height as f64), size);
+    for i in rows {
+        for j in columns.clone() {
             if !state_types.is_empty() {
                 ctx.set_fill_style(&wasm_bindgen::JsValue::from_str(
-                    state_types[grid[i][j]].color.as_str(),
+                    state_types[grid.get(j, i)].color.as_str(),
                 ));
             } else {
                 ctx.set_fill_style(&wasm_bindgen::JsValue::from_str("#000000"));
             }
 
             ctx.fill_rect(
-                (j as i32 * cell_size) as f64,
-                (i as i32 * cell_size) as f64,
-                cell_size as f64,
-                cell_size as f64,
+                viewport.x + j as f64 * viewport.cell_size,
+                viewport.y + i as f64 * viewport.cell_size,
+                viewport.cell_size,
+                viewport.cell_size,
             );
         }
     }
-    render_grid(canvas_ref, width, height, cell_size);
+    render_grid(&ctx, viewport, size);
 }
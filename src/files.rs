@@ -0,0 +1,300 @@
+use crate::canvas::edit_board;
+use crate::history::History;
+use crate::life::*;
+use crate::presets::PRESETS;
+use crate::project::Project;
+use crate::viewport::Viewport;
+use crate::{rle, rulestring};
+use leptos::html::Canvas;
+use leptos::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+#[component]
+pub fn Files(
+    canvas_ref: NodeRef<html::Canvas>,
+    width: ReadSignal<i32>,
+    height: ReadSignal<i32>,
+    viewport: ReadSignal<Viewport>,
+    r_board: ReadSignal<Board>,
+    w_board: WriteSignal<Board>,
+    render_board: fn(HtmlElement<Canvas>, i32, i32, &Viewport, &Board),
+    set_states_revision: WriteSignal<usize>,
+    delay: ReadSignal<u64>,
+    set_delay: WriteSignal<u64>,
+    history: StoredValue<History>,
+) -> impl IntoView {
+    let (status, set_status) = create_signal(String::new());
+
+    // Writes the smallest rectangle holding every non-zero cell
+    let export_rle = move || {
+        r_board.with(|b| {
+            let rule = rulestring::format(&b.state_types, &b.neighborhood).ok();
+            let cells = match b.grid.bounding_box() {
+                Some((x, y, w, h)) => b.grid.region(x, y, w, h),
+                None => Grid::default(),
+            };
+            rle::write(&cells, rule.as_deref())
+        })
+    };
+
+    // Clears the board and centers the pattern on it, switching to the
+    // pattern's rule if it has one that differs from the current rule
+    let import_rle = move |text: String| {
+        let pattern = match rle::parse(&text) {
+            Ok(pattern) => pattern,
+            Err(e) => return set_status(e),
+        };
+        let rule = match pattern.rule.as_deref().map(rulestring::parse) {
+            Some(Ok((states, neighborhood))) => {
+                let current = r_board.with(|b| rulestring::format(&b.state_types, &b.neighborhood));
+                (current != rulestring::format(&states, &neighborhood))
+                    .then_some((states, neighborhood))
+            }
+            Some(Err(e)) => return set_status(format!("couldn't read the pattern's rule: {}", e)),
+            None => None,
+        };
+        let states = rule
+            .as_ref()
+            .map_or(r_board().state_types.len(), |(states, _)| states.len());
+        if let Some(state) = pattern.cells.cells().iter().find(|&&c| c as usize >= states) {
+            return set_status(format!(
+                "the pattern uses state {} but there are only {} states",
+                state, states
+            ));
+        }
+
+        // Batched, so the state editor is rebuilt before it sees fewer states
+        let new_rule = rule.is_some();
+        batch(|| {
+            edit_board(r_board, w_board, history, |b| {
+                if let Some((states, neighborhood)) = rule {
+                    b.set_state_types(states);
+                    b.neighborhood = neighborhood;
+                }
+                let (w, h) = (b.grid.width() as i32, b.grid.height() as i32);
+                b.grid = Grid::new(w as usize, h as usize);
+                b.grid.paste(
+                    &pattern.cells,
+                    (w - pattern.width as i32) / 2,
+                    (h - pattern.height as i32) / 2,
+                );
+            });
+            if new_rule {
+                set_states_revision.update(|r| *r += 1);
+            }
+        });
+        set_status(String::new());
+        render_board(
+            canvas_ref.get().unwrap(),
+            width(),
+            height(),
+            &viewport(),
+            &r_board(),
+        );
+    };
+
+    let load_project = move |json: String| {
+        let project = match Project::from_json(&json) {
+            Ok(project) => project,
+            Err(e) => return set_status(e),
+        };
+        set_delay(project.delay);
+        let board = project.into_board();
+        batch(|| {
+            edit_board(r_board, w_board, history, |b| *b = board);
+            set_states_revision.update(|r| *r += 1);
+        });
+        set_status(String::new());
+        render_board(
+            canvas_ref.get().unwrap(),
+            width(),
+            height(),
+            &viewport(),
+            &r_board(),
+        );
+    };
+
+    let reset = move || {
+        let (w, h) = r_board.with(|b| (b.grid.width(), b.grid.height()));
+        set_delay(1000);
+        batch(|| {
+            edit_board(r_board, w_board, history, |b| *b = Board::new(w, h, None));
+            set_states_revision.update(|r| *r += 1);
+        });
+        set_status(String::new());
+        render_board(
+            canvas_ref.get().unwrap(),
+            width(),
+            height(),
+            &viewport(),
+            &r_board(),
+        );
+    };
+
+    // Swaps in the preset's rules and demo at the current board size
+    let load_preset = move |index: usize| {
+        let Some(preset) = PRESETS.get(index) else {
+            return;
+        };
+        let (w, h) = r_board.with(|b| (b.grid.width(), b.grid.height()));
+        batch(|| {
+            edit_board(r_board, w_board, history, |b| *b = preset.board(w, h));
+            set_states_revision.update(|r| *r += 1);
+        });
+        set_status(String::new());
+        render_board(
+            canvas_ref.get().unwrap(),
+            width(),
+            height(),
+            &viewport(),
+            &r_board(),
+        );
+    };
+
+    view! {
+        <div>
+            "Project: "
+            <button on:click=move |_| {
+                let json = r_board.with(|b| Project::new(b, delay()).to_json());
+                download("automaton.json", &json);
+            }>"Save Project"</button>
+            " Load Project "
+            <input
+                type="file"
+                accept=".json"
+                on:change=move |ev| {
+                    let input = event_target::<web_sys::HtmlInputElement>(&ev);
+                    spawn_local(async move {
+                        if let Some(json) = read_file(input).await {
+                            load_project(json);
+                        }
+                    });
+                }
+            />
+            <button on:click=move |_| reset()>"Reset to Game of Life"</button>
+            " Preset "
+            <select on:input=move |ev| {
+                let select = event_target::<web_sys::HtmlSelectElement>(&ev);
+                if let Ok(index) = select.value().parse::<usize>() {
+                    load_preset(index);
+                }
+                select.set_value("");
+            }>
+                <option value="">"Choose a preset"</option>
+                {PRESETS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, preset)| view! { <option value=i>{preset.name}</option> })
+                    .collect_view()}
+            </select>
+            <button on:click=move |_| {
+                let url = r_board.with(|b| link(&Project::new(b, delay())));
+                let _ = clipboard().write_text(&url);
+                set_status(String::from("Link copied"));
+            }>"Copy Link"</button>
+        </div>
+        <div>
+            "Pattern (RLE): "
+            <button on:click=move |_| download("pattern.rle", &export_rle())>"Save RLE"</button>
+            " Load RLE "
+            <input
+                type="file"
+                accept=".rle,.txt"
+                on:change=move |ev| {
+                    let input = event_target::<web_sys::HtmlInputElement>(&ev);
+                    spawn_local(async move {
+                        if let Some(text) = read_file(input).await {
+                            import_rle(text);
+                        }
+                    });
+                }
+            />
+            <button on:click=move |_| {
+                let _ = clipboard().write_text(&export_rle());
+            }>"Copy RLE"</button>
+            <button on:click=move |_| {
+                spawn_local(async move {
+                    match read_clipboard().await {
+                        Some(text) => import_rle(text),
+                        None => set_status(String::from("couldn't read the clipboard")),
+                    }
+                });
+            }>"Paste RLE"</button>
+            " "
+            {status}
+        </div>
+    }
+}
+
+const LINK_PREFIX: &str = "#p=";
+
+// A link that opens the current automaton, carried in the URL fragment
+pub fn link(project: &Project) -> String {
+    let location = window().location();
+    let page = location.href().unwrap_or_default();
+    let page = page.split('#').next().unwrap_or_default();
+    format!("{}{}{}", page, LINK_PREFIX, project.to_link())
+}
+
+// Reads the project out of the address bar and then clears it, so that
+// reloading the page later restores the session instead of the link. None
+// when there's no link, and an error when there's one that can't be opened.
+pub fn take_link() -> Option<Result<Project, String>> {
+    let hash = window().location().hash().ok()?;
+    let project = Project::from_link(hash.strip_prefix(LINK_PREFIX)?);
+    if let Ok(history) = window().history() {
+        let path = window().location().pathname().unwrap_or_default();
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&path));
+    }
+    Some(project)
+}
+
+const SESSION_KEY: &str = "ca-generalized-session";
+
+// The automaton as it was when the page was last open, if the browser kept it
+pub fn load_session() -> Option<Result<Project, String>> {
+    let storage = window().local_storage().ok()??;
+    let json = storage.get_item(SESSION_KEY).ok()??;
+    Some(Project::from_json(&json))
+}
+
+// Storage can be full or turned off, in which case the session just isn't kept
+pub fn save_session(project: &Project) {
+    if let Ok(Some(storage)) = window().local_storage() {
+        let _ = storage.set_item(SESSION_KEY, &project.to_compact_json());
+    }
+}
+
+// Saves text to a file by clicking a temporary download link
+pub fn download(filename: &str, contents: &str) {
+    let parts = js_sys::Array::of1(&wasm_bindgen::JsValue::from_str(contents));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("text/plain");
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).unwrap();
+    let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap();
+    let link = document()
+        .create_element("a")
+        .unwrap()
+        .unchecked_into::<web_sys::HtmlAnchorElement>();
+    link.set_href(&url);
+    link.set_download(filename);
+    link.click();
+    let _ = web_sys::Url::revoke_object_url(&url);
+}
+
+// Reads the first file chosen in a file input as text
+pub async fn read_file(input: web_sys::HtmlInputElement) -> Option<String> {
+    let file = input.files()?.get(0)?;
+    JsFuture::from(file.text()).await.ok()?.as_string()
+}
+
+pub fn clipboard() -> web_sys::Clipboard {
+    window().navigator().clipboard()
+}
+
+pub async fn read_clipboard() -> Option<String> {
+    JsFuture::from(clipboard().read_text())
+        .await
+        .ok()?
+        .as_string()
+}
@@ -0,0 +1,147 @@
+use crate::brush::Tool;
+use crate::life::*;
+use crate::patterns::{self, Category, CATEGORIES};
+use crate::presets;
+use crate::rulestring;
+use crate::viewport::Viewport;
+use leptos::html::Canvas;
+use leptos::*;
+
+#[component]
+pub fn Library(
+    r_board: ReadSignal<Board>,
+    render_board: fn(HtmlElement<Canvas>, i32, i32, &Viewport, &Board),
+    stamp: ReadSignal<Option<Grid>>,
+    set_stamp: WriteSignal<Option<Grid>>,
+    set_tool: WriteSignal<Tool>,
+) -> impl IntoView {
+    let (category, set_category) = create_signal(Category::Spaceship);
+    // Rules without a rulestring go by the preset they come from
+    let rule = create_memo(move |_| {
+        r_board.with(|b| {
+            rulestring::format(&b.state_types, &b.neighborhood)
+                .ok()
+                .or_else(|| presets::matching(b).map(|preset| preset.name.to_string()))
+                .unwrap_or_default()
+        })
+    });
+    let entries = move || {
+        patterns::for_rule(&rule())
+            .filter(|entry| entry.category == category())
+            .collect::<Vec<_>>()
+    };
+    let (name, set_name) = create_signal(String::new());
+
+    // A pattern picked under other rules may use states this board doesn't have
+    create_effect(move |_| {
+        let states = r_board.with(|b| b.state_types.len());
+        if stamp.with(|s| s.as_ref().is_some_and(|cells| !fits(cells, states))) {
+            set_stamp(None);
+            set_name(String::new());
+        }
+    });
+
+    let choose = move |entry_name: String| {
+        let entry = patterns::for_rule(&rule()).find(|entry| entry.name == entry_name);
+        if let Some(entry) = entry {
+            set_stamp(Some(entry.cells()));
+            set_tool(Tool::Stamp);
+        }
+        set_name(entry_name);
+    };
+
+    // Draws the stamp as large as fits, in the board's colors
+    let preview_ref: NodeRef<html::Canvas> = create_node_ref();
+    create_effect(move |_| {
+        let Some(canvas) = preview_ref.get() else {
+            return;
+        };
+        let mut preview = r_board.with(|b| Board::new(0, 0, Some(b.state_types.clone())));
+        let mut viewport = Viewport::new(1.0);
+        if let Some(cells) = stamp().filter(|cells| fits(cells, preview.state_types.len())) {
+            viewport.fit(
+                (0, 0, cells.width(), cells.height()),
+                PREVIEW_SIZE as f64,
+                PREVIEW_SIZE as f64,
+            );
+            preview.grid = cells;
+        }
+        render_board(canvas, PREVIEW_SIZE, PREVIEW_SIZE, &viewport, &preview);
+    });
+
+    view! {
+        <div>
+            "Patterns: "
+            <select on:input=move |ev| {
+                if let Some(&c) = event_target_value(&ev)
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| CATEGORIES.get(i))
+                {
+                    set_category(c);
+                }
+            }>
+                {CATEGORIES
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &c)| {
+                        view! {
+                            <option value=i selected=move || category() == c>
+                                {c.name()}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
+            </select>
+            " "
+            {move || {
+                let entries = entries();
+                if entries.is_empty() {
+                    return view! { "none for this rule" }.into_view();
+                }
+                view! {
+                    <select on:input=move |ev| choose(event_target_value(&ev))>
+                        <option value="" selected=move || name().is_empty()>
+                            "Choose a pattern"
+                        </option>
+                        {entries
+                            .into_iter()
+                            .map(|entry| {
+                                view! {
+                                    <option
+                                        value=entry.name
+                                        selected=move || name() == entry.name
+                                    >
+                                        {entry.name}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                }
+                    .into_view()
+            }}
+            " "
+            <button
+                disabled=move || stamp.with(|s| s.is_none())
+                on:click=move |_| set_stamp.update(|s| *s = s.as_ref().map(Grid::rotated))
+            >
+                "Rotate 90°"
+            </button>
+            <button
+                disabled=move || stamp.with(|s| s.is_none())
+                on:click=move |_| set_tool(Tool::Stamp)
+            >
+                "Stamp"
+            </button>
+        </div>
+        <canvas width=PREVIEW_SIZE height=PREVIEW_SIZE class="canvas" node_ref=preview_ref></canvas>
+    }
+}
+
+pub fn fits(cells: &Grid, states: usize) -> bool {
+    cells.cells().iter().all(|&c| (c as usize) < states)
+}
+
+// How many pixels across the preview is
+const PREVIEW_SIZE: i32 = 128;
@@ -1,10 +1,18 @@
 //#[allow(warnings)]
 pub mod canvas;
-pub mod life;
+pub mod files;
+pub mod library;
+pub mod one_dimensional;
+pub mod selection;
 pub mod settings;
 pub mod states;
+pub use ca_engine::{
+    brush, elementary, hashlife, history, life, patterns, presets, project, random, rle,
+    rulestring, timeline, universe, viewport,
+};
 use canvas::Canvas;
 use leptos::*;
+use one_dimensional::OneDimensional;
 extern crate console_error_panic_hook;
 use std::panic;
 
@@ -15,5 +23,23 @@ fn main() {
 
 #[component]
 fn App() -> impl IntoView {
-    view! { <Canvas/> }
+    // Both stay mounted so switching back and forth keeps where each was up to.
+    // The hidden one stops playing and leaves the keyboard alone.
+    let (one_dimensional, set_one_dimensional) = create_signal(false);
+    let two_dimensional = Signal::derive(move || !one_dimensional());
+    view! {
+        <div>
+            "Mode: "
+            <select on:change=move |ev| set_one_dimensional(event_target_value(&ev) == "1d")>
+                <option value="2d">"2D"</option>
+                <option value="1d">"1D"</option>
+            </select>
+        </div>
+        <div style:display=move || if one_dimensional() { "none" } else { "block" }>
+            <Canvas active=two_dimensional/>
+        </div>
+        <div style:display=move || if one_dimensional() { "block" } else { "none" }>
+            <OneDimensional active=one_dimensional/>
+        </div>
+    }
 }
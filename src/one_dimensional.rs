@@ -0,0 +1,254 @@
+use crate::elementary::{self, Rule, Spacetime, MAX_COLORS, MAX_RADIUS};
+use crate::life::Cell;
+use js_sys::Math::random;
+use leptos::*;
+use std::time::Duration;
+use wasm_bindgen::JsCast;
+
+// Runs a one dimensional automaton, drawing each generation as the next row
+// down the canvas and scrolling once it reaches the bottom
+#[component]
+pub fn OneDimensional(active: ReadSignal<bool>) -> impl IntoView {
+    let (width, _) = create_signal(1024);
+    let (height, _) = create_signal(512);
+    let canvas_ref: NodeRef<html::Canvas> = create_node_ref();
+
+    let (totalistic, set_totalistic) = create_signal(false);
+    let (number, set_number) = create_signal(String::from("30"));
+    let (colors, set_colors) = create_signal(3usize);
+    let (radius, set_radius) = create_signal(1usize);
+    let (cells, set_cells) = create_signal(DEFAULT_CELLS);
+    let (random_start, set_random_start) = create_signal(false);
+    let (delay, set_delay) = create_signal(50u64);
+    let (error, set_error) = create_signal(String::new());
+    let (generation, set_generation) = create_signal(0usize);
+
+    let rule = move || -> Result<Rule, String> {
+        if totalistic() {
+            let code = number()
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| String::from("the code should be a whole number"))?;
+            Rule::totalistic(colors(), radius(), code)
+        } else {
+            let number = number()
+                .trim()
+                .parse::<u8>()
+                .map_err(|_| String::from("rule numbers go from 0 to 255"))?;
+            Ok(Rule::Elementary(number))
+        }
+    };
+
+    let cell_size = move || (width() as f64 / cells() as f64).max(1.0);
+    let spacetime = store_value(None::<Spacetime>);
+    let redraw = move || {
+        let Some(canvas) = canvas_ref.get_untracked() else {
+            return;
+        };
+        spacetime.with_value(|s| {
+            if let Some(s) = s {
+                render_spacetime(canvas, width(), height(), cell_size(), s);
+            }
+        });
+    };
+
+    // Starts again from a fresh first row under the rule as it's set now
+    let restart = move || {
+        let rule = match rule() {
+            Ok(rule) => rule,
+            Err(e) => return set_error(e),
+        };
+        set_error(String::new());
+        let first = if random_start() {
+            (0..cells())
+                .map(|_| (random() * rule.colors() as f64) as Cell)
+                .collect()
+        } else {
+            elementary::single_cell(cells())
+        };
+        let rows = (height() as f64 / cell_size()) as usize;
+        spacetime.set_value(Some(Spacetime::new(rule, &first, rows)));
+        set_generation(0);
+        redraw();
+    };
+
+    let step = move || {
+        spacetime.update_value(|s| {
+            if let Some(s) = s {
+                s.step();
+                set_generation(s.generation);
+            }
+        });
+        redraw();
+    };
+
+    let (paused, set_paused) = create_signal(true);
+    let handle = store_value(None::<IntervalHandle>);
+    let stop = move || {
+        if let Some(h) = handle.get_value() {
+            h.clear();
+        }
+        handle.set_value(None);
+    };
+    create_effect(move |_| {
+        stop();
+        if !paused() && active() {
+            handle.set_value(set_interval_with_handle(step, Duration::from_millis(delay())).ok());
+        }
+    });
+
+    canvas_ref.on_load(move |canvas_ref| {
+        canvas_ref.on_mount(move |_| restart());
+    });
+
+    view! {
+        <div>
+            "Rule: "
+            <select on:change=move |ev| {
+                let to_totalistic = event_target_value(&ev) == "totalistic";
+                set_number(String::from(if to_totalistic { "777" } else { "30" }));
+                set_totalistic(to_totalistic);
+                restart();
+            }>
+                <option value="elementary" selected=move || !totalistic()>
+                    "Elementary"
+                </option>
+                <option value="totalistic" selected=totalistic>
+                    "Totalistic"
+                </option>
+            </select>
+            " "
+            {move || if totalistic() { "Code " } else { "Number " }}
+            <input
+                type="text"
+                size="20"
+                prop:value=number
+                on:change=move |ev| {
+                    set_number(event_target_value(&ev));
+                    restart();
+                }
+            />
+            <span style:display=move || if totalistic() { "inline" } else { "none" }>
+                " Colors "
+                <input
+                    type="number"
+                    min="2"
+                    max=MAX_COLORS
+                    prop:value=colors
+                    on:change=move |ev| {
+                        if let Ok(c) = event_target_value(&ev).parse() {
+                            set_colors(c);
+                            restart();
+                        }
+                    }
+                />
+                " Radius "
+                <input
+                    type="number"
+                    min="1"
+                    max=MAX_RADIUS
+                    prop:value=radius
+                    on:change=move |ev| {
+                        if let Ok(r) = event_target_value(&ev).parse() {
+                            set_radius(r);
+                            restart();
+                        }
+                    }
+                />
+            </span>
+            " "
+            {error}
+        </div>
+        <div>
+            "Cells across "
+            <input
+                type="number"
+                min="1"
+                max=width
+                prop:value=cells
+                on:change=move |ev| {
+                    if let Ok(n) = event_target_value(&ev).parse::<usize>() {
+                        set_cells(n.clamp(1, width() as usize));
+                        restart();
+                    }
+                }
+            />
+            " Start from "
+            <select on:change=move |ev| {
+                set_random_start(event_target_value(&ev) == "random");
+                restart();
+            }>
+                <option value="single" selected=move || !random_start()>
+                    "One Cell"
+                </option>
+                <option value="random" selected=random_start>
+                    "Random Row"
+                </option>
+            </select>
+            <button on:click=move |_| restart()>"Restart"</button>
+        </div>
+        <canvas width=width height=height class="canvas" node_ref=canvas_ref></canvas>
+        <div>
+            "Generation " {generation} " "
+            <input
+                type="button"
+                value=move || if paused() { "play" } else { "pause" }
+                on:click=move |_| set_paused.update(|p| *p = !*p)
+            />
+            <button on:click=move |_| step()>"Step"</button>
+            " Delay: " {delay}
+            <input
+                type="range"
+                min="10"
+                max="1000"
+                step="10"
+                prop:value=delay
+                on:input=move |ev| {
+                    if let Ok(d) = event_target_value(&ev).parse() {
+                        set_delay(d);
+                    }
+                }
+            />
+        </div>
+    }
+}
+
+// How many cells the row starts with
+const DEFAULT_CELLS: usize = 256;
+
+// States shade from white down to black, so two color rules come out black
+// on white
+fn shade(state: usize, colors: usize) -> String {
+    let level = 255 - 255 * state / (colors - 1).max(1);
+    format!("#{0:02X}{0:02X}{0:02X}", level)
+}
+
+fn render_spacetime(
+    canvas: HtmlElement<html::Canvas>,
+    width: i32,
+    height: i32,
+    cell_size: f64,
+    spacetime: &Spacetime,
+) {
+    let ctx = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .expect("canvas to have context")
+        .unchecked_into::<web_sys::CanvasRenderingContext2d>();
+    ctx.clear_rect(0.0, 0.0, width as f64, height as f64);
+    let colors = spacetime.rule.colors();
+    let shades = (0..colors).map(|s| shade(s, colors)).collect::<Vec<_>>();
+    let grid = spacetime.grid();
+    for (y, row) in grid.rows().take(spacetime.rows()).enumerate() {
+        for (x, &cell) in row.iter().enumerate() {
+            ctx.set_fill_style(&wasm_bindgen::JsValue::from_str(&shades[cell as usize]));
+            ctx.fill_rect(
+                x as f64 * cell_size,
+                y as f64 * cell_size,
+                cell_size,
+                cell_size,
+            );
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::canvas::edit_board;
+use crate::files::{clipboard, read_clipboard};
+use crate::history::History;
+use crate::life::*;
+use crate::viewport::Viewport;
+use crate::{rle, rulestring};
+use js_sys::Math::random;
+use leptos::html::Canvas;
+use leptos::*;
+use wasm_bindgen::JsCast;
+
+// A selected (x, y, width, height) rectangle of cells
+pub type Rect = (usize, usize, usize, usize);
+
+#[component]
+pub fn Selection(
+    canvas_ref: NodeRef<html::Canvas>,
+    width: ReadSignal<i32>,
+    height: ReadSignal<i32>,
+    viewport: ReadSignal<Viewport>,
+    r_board: ReadSignal<Board>,
+    w_board: WriteSignal<Board>,
+    render_board: fn(HtmlElement<Canvas>, i32, i32, &Viewport, &Board),
+    selection: ReadSignal<Option<Rect>>,
+    set_selection: WriteSignal<Option<Rect>>,
+    history: StoredValue<History>,
+    active: Signal<bool>,
+) -> impl IntoView {
+    let (status, set_status) = create_signal(String::new());
+    // Copies are kept here as well as on the system clipboard, which the
+    // browser may not let us read back
+    let copied = store_value(None::<Grid>);
+
+    let redraw = move || {
+        render_board(
+            canvas_ref.get().unwrap(),
+            width(),
+            height(),
+            &viewport(),
+            &r_board(),
+        )
+    };
+    // The selection may have been made before the board shrank
+    let selected = move || {
+        let (x, y, w, h) = selection.get_untracked()?;
+        r_board.with_untracked(|b| {
+            let (width, height) = (b.grid.width(), b.grid.height());
+            (x < width && y < height).then(|| (x, y, w.min(width - x), h.min(height - y)))
+        })
+    };
+
+    let copy = move || {
+        let Some((x, y, w, h)) = selected() else {
+            return;
+        };
+        let (cells, rule) = r_board.with_untracked(|b| {
+            let rule = rulestring::format(&b.state_types, &b.neighborhood).ok();
+            (b.grid.region(x, y, w, h), rule)
+        });
+        let _ = clipboard().write_text(&rle::write(&cells, rule.as_deref()));
+        copied.set_value(Some(cells));
+    };
+    let clear = move || {
+        let Some((x, y, w, h)) = selected() else {
+            return;
+        };
+        edit_board(r_board, w_board, history, |b| b.grid.fill(x, y, w, h, || 0));
+        redraw();
+    };
+    let cut = move || {
+        copy();
+        clear();
+    };
+    let fill_random = move || {
+        let Some((x, y, w, h)) = selected() else {
+            return;
+        };
+        let states = r_board.with_untracked(|b| b.state_types.len());
+        edit_board(r_board, w_board, history, |b| {
+            b.grid
+                .fill(x, y, w, h, || (random() * states as f64) as usize)
+        });
+        redraw();
+    };
+
+    // Puts cells at the selection's corner, or the corner of the view when
+    // nothing is selected, and selects them
+    let place = move |cells: Grid| {
+        let states = r_board.with_untracked(|b| b.state_types.len());
+        if let Some(state) = cells.cells().iter().find(|&&c| c as usize >= states) {
+            return set_status(format!(
+                "the pattern uses state {} but there are only {} states",
+                state, states
+            ));
+        }
+        let (x, y) = selected().map_or_else(
+            || {
+                let (x, y) = viewport.get_untracked().cell(0.0, 0.0);
+                (x.max(0) as usize, y.max(0) as usize)
+            },
+            |(x, y, _, _)| (x, y),
+        );
+        edit_board(r_board, w_board, history, |b| {
+            b.grid.paste(&cells, x as i32, y as i32)
+        });
+        set_selection(Some((x, y, cells.width(), cells.height())));
+        set_status(String::new());
+        redraw();
+    };
+    let paste = move || {
+        spawn_local(async move {
+            let pasted = read_clipboard()
+                .await
+                .and_then(|text| rle::parse(&text).ok())
+                .map(|pattern| pattern.cells)
+                .or_else(|| copied.get_value());
+            match pasted {
+                Some(cells) => place(cells),
+                None => set_status(String::from("there's nothing to paste")),
+            }
+        });
+    };
+
+    // Replaces the selected cells with a rotated or flipped copy, keeping the
+    // top left corner where it is
+    let transform = move |f: fn(&Grid) -> Grid| {
+        let Some((x, y, w, h)) = selected() else {
+            return;
+        };
+        let cells = r_board.with_untracked(|b| f(&b.grid.region(x, y, w, h)));
+        edit_board(r_board, w_board, history, |b| {
+            b.grid.fill(x, y, w, h, || 0);
+            b.grid.paste(&cells, x as i32, y as i32);
+        });
+        set_selection(Some((x, y, cells.width(), cells.height())));
+        redraw();
+    };
+
+    // Ctrl+C, Ctrl+X and Ctrl+V copy, cut and paste, Delete clears and Escape
+    // lets go of the selection
+    _ = window_event_listener(ev::keydown, move |ev| {
+        let in_text_box = ev
+            .target()
+            .is_some_and(|t| t.dyn_ref::<web_sys::HtmlInputElement>().is_some());
+        if in_text_box || !active() {
+            return;
+        }
+        let ctrl = ev.ctrl_key() || ev.meta_key();
+        let any = selected().is_some();
+        match ev.key().to_lowercase().as_str() {
+            "c" if ctrl && any => copy(),
+            "x" if ctrl && any => cut(),
+            "v" if ctrl => paste(),
+            "delete" | "backspace" if any => clear(),
+            "escape" if any => set_selection(None),
+            _ => return,
+        }
+        ev.prevent_default();
+    });
+
+    let none_selected = move || selection().is_none();
+    view! {
+        <div>
+            "Selection: "
+            {move || match selection() {
+                Some((x, y, w, h)) => format!("{}x{} at ({}, {}) ", w, h, x, y),
+                None => String::from("none "),
+            }}
+            <button disabled=none_selected on:click=move |_| copy()>"Copy"</button>
+            <button disabled=none_selected on:click=move |_| cut()>"Cut"</button>
+            <button on:click=move |_| paste()>"Paste"</button>
+            " "
+            <button disabled=none_selected on:click=move |_| transform(Grid::rotated)>
+                "Rotate 90°"
+            </button>
+            <button
+                disabled=none_selected
+                on:click=move |_| transform(Grid::flipped_horizontally)
+            >
+                "Flip Horizontal"
+            </button>
+            <button
+                disabled=none_selected
+                on:click=move |_| transform(Grid::flipped_vertically)
+            >
+                "Flip Vertical"
+            </button>
+            " "
+            <button disabled=none_selected on:click=move |_| clear()>"Clear"</button>
+            <button disabled=none_selected on:click=move |_| fill_random()>"Fill Random"</button>
+            <button disabled=none_selected on:click=move |_| set_selection(None)>"Deselect"</button>
+            " "
+            {status}
+        </div>
+    }
+}
+
+// Outlines the selection on the overlay canvas that sits over the board
+pub fn render_selection(
+    canvas_ref: HtmlElement<Canvas>,
+    width: i32,
+    height: i32,
+    viewport: &Viewport,
+    selection: Option<Rect>,
+) {
+    let ctx = canvas_ref
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .expect("canvas to have context")
+        .unchecked_into::<web_sys::CanvasRenderingContext2d>();
+    ctx.clear_rect(0.0, 0.0, width as f64, height as f64);
+    let Some((x, y, w, h)) = selection else {
+        return;
+    };
+    let size = viewport.cell_size;
+    let (left, top) = (viewport.x + x as f64 * size, viewport.y + y as f64 * size);
+    ctx.set_fill_style(&wasm_bindgen::JsValue::from_str("rgba(255, 215, 0, 0.2)"));
+    ctx.fill_rect(left, top, w as f64 * size, h as f64 * size);
+    ctx.set_stroke_style(&wasm_bindgen::JsValue::from_str("#FFD700"));
+    ctx.set_line_width(2.0);
+    ctx.stroke_rect(left, top, w as f64 * size, h as f64 * size);
+    ctx.set_line_width(1.0);
+}
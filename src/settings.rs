@@ -1,61 +1,203 @@
+use crate::canvas::edit_board;
+use crate::history::History;
 use crate::life::*;
+use crate::project::MAX_SIZE;
+use crate::random::Random;
+use crate::{hashlife, rulestring};
+use crate::timeline::{self, Timeline};
+use crate::universe::Universe;
+use crate::viewport::{self, Viewport};
 use js_sys::Math::random;
 use leptos::html::Canvas;
 use leptos::*;
 use std::time::Duration;
 use wasm_bindgen::prelude::*;
-use wasm_bindgen::JsCast;
 #[component]
 pub fn Settings(
     canvas_ref: NodeRef<html::Canvas>,
     width: ReadSignal<i32>,
     height: ReadSignal<i32>,
-    r_cell_size: ReadSignal<i32>,
-    w_cell_size: WriteSignal<i32>,
+    viewport: ReadSignal<Viewport>,
+    set_viewport: WriteSignal<Viewport>,
     r_board: ReadSignal<Board>,
     w_board: WriteSignal<Board>,
-    render_grid: fn(HtmlElement<Canvas>, i32, i32, i32),
-    render_board: fn(HtmlElement<Canvas>, i32, i32, i32, &Board),
+    render_board: fn(HtmlElement<Canvas>, i32, i32, &Viewport, &Board),
+    set_states_revision: WriteSignal<usize>,
+    delay: ReadSignal<u64>,
+    set_delay: WriteSignal<u64>,
+    history: StoredValue<History>,
+    active: Signal<bool>,
 ) -> impl IntoView {
     let (handle, set_handle): (
         ReadSignal<Option<Result<IntervalHandle, JsValue>>>,
         WriteSignal<Option<Result<IntervalHandle, JsValue>>>,
     ) = create_signal(None);
-    let w = move || (width() / r_cell_size()) as usize;
-    let h = move || (height() / r_cell_size()) as usize;
     let (paused, set_paused) = create_signal(true);
-    let (delay, set_delay) = create_signal(1000);
 
+    // Zooms around the middle of the canvas, leaving the board as it is
     let slider_function = move |input: ev::Event| {
-        input.prevent_default();
-        w_cell_size(event_target_value(&input).parse().unwrap());
-        w_board(Board::new(w(), h(), None));
+        let Ok(cell_size) = event_target_value(&input).parse::<f64>() else {
+            return;
+        };
+        set_viewport.update(|v| {
+            v.zoom(
+                cell_size / v.cell_size,
+                width() as f64 / 2.0,
+                height() as f64 / 2.0,
+            )
+        });
+        render_board(
+            canvas_ref.get().unwrap(),
+            width(),
+            height(),
+            &viewport(),
+            &r_board(),
+        );
+    };
 
-        let ctx = canvas_ref
-            .get()
-            .unwrap()
-            .get_context("2d")
-            .ok()
-            .flatten()
-            .expect("canvas to have context")
-            .unchecked_into::<web_sys::CanvasRenderingContext2d>();
-        ctx.clear_rect(0.0, 0.0, width() as f64, height() as f64);
+    let (timeline, set_timeline) = create_signal(Timeline::new(
+        r_board.get_untracked().grid,
+        timeline::DEFAULT_CAPACITY,
+    ));
 
-        render_grid(canvas_ref.get().unwrap(), width(), height(), r_cell_size());
+    // While unbounded the board is a window onto the universe, with its top
+    // left cell at origin. The window moves and resizes to cover whatever the
+    // canvas shows, so the view can be panned out past where the board was.
+    let (unbounded, set_unbounded) = create_signal(false);
+    let (universe_error, set_universe_error) = create_signal(String::new());
+    let universe = store_value(None::<Universe>);
+    let origin = store_value((0i64, 0i64));
+    let restart_universe = move |on: bool| {
+        universe.set_value(on.then(|| r_board.with_untracked(Universe::from_board)));
+        origin.set_value((0, 0));
     };
 
     let update = move || {
-        let next = r_board().next();
-        w_board.update(|b| b.grid = next);
+        let before = r_board.with_untracked(|b| b.grid.clone());
+        if unbounded.get_untracked() {
+            let mut result = Ok(());
+            w_board.update(|b| {
+                universe.update_value(|u| {
+                    let u = u.get_or_insert_with(|| Universe::from_board(b));
+                    // Picks up any rule changes and edits made since the last step
+                    u.set_rules(&b.state_types, &b.neighborhood);
+                    let (x, y) = origin.get_value();
+                    u.paste(&b.grid, x, y);
+                    result = u.step();
+                    if result.is_ok() {
+                        b.grid = u.region(x, y, b.grid.width(), b.grid.height());
+                    }
+                })
+            });
+            if let Err(e) = result {
+                set_universe_error(e);
+                set_unbounded(false);
+                restart_universe(false);
+                return;
+            }
+        } else {
+            w_board.update(|b| b.step());
+        }
+        r_board.with_untracked(|b| set_timeline.update(|t| t.record(&before, &b.grid)));
+        render_board(
+            canvas_ref.get().unwrap(),
+            width(),
+            height(),
+            &viewport(),
+            &r_board(),
+        );
+    };
+
+    // Moves the window over the cells on the canvas, shifting the view to
+    // match so the picture stays where it is
+    let follow = move || {
+        let v = viewport.get_untracked();
+        let (left, top) = v.cell(0.0, 0.0);
+        let (right, bottom) = v.cell(width() as f64 - 1.0, height() as f64 - 1.0);
+        let w = ((right - left + 1) as usize).clamp(1, MAX_SIZE);
+        let h = ((bottom - top + 1) as usize).clamp(1, MAX_SIZE);
+        let same_size = r_board.with_untracked(|b| (b.grid.width(), b.grid.height()) == (w, h));
+        if (left, top) == (0, 0) && same_size {
+            return;
+        }
+        let (x, y) = origin.get_value();
+        w_board.update(|b| {
+            universe.update_value(|u| {
+                if let Some(u) = u {
+                    u.paste(&b.grid, x, y);
+                    b.grid = u.region(x + left, y + top, w, h);
+                }
+            })
+        });
+        origin.set_value((x + left, y + top));
+        // Recorded edits are at the old window's coordinates
+        history.update_value(|h| h.clear());
+        set_viewport.update(|v| v.pan(left as f64 * v.cell_size, top as f64 * v.cell_size));
+        if let Some(canvas) = canvas_ref.get_untracked() {
+            let v = viewport.get_untracked();
+            r_board.with_untracked(|b| render_board(canvas, width(), height(), &v, b));
+        }
+    };
+    create_effect(move |_| {
+        viewport.track();
+        if unbounded() {
+            follow();
+        }
+    });
+
+    // Shows a recorded generation, which the simulation then carries on from
+    let seek = move |generation: usize| {
+        let mut grid = None;
+        set_timeline.update(|t| grid = t.seek(generation).cloned());
+        let Some(mut grid) = grid else { return };
+        // The states may have been removed since this generation was recorded
+        let states = r_board.with_untracked(|b| b.state_types.len());
+        for cell in grid.cells_mut() {
+            if *cell as usize >= states {
+                *cell = 0;
+            }
+        }
+        w_board.update(|b| b.grid = grid);
+        // Whatever was outside the window at that generation wasn't recorded
+        if unbounded.get_untracked() {
+            restart_universe(true);
+        }
         render_board(
             canvas_ref.get().unwrap(),
             width(),
             height(),
-            r_cell_size(),
+            &viewport(),
             &r_board(),
         );
     };
 
+    let (jump_log2, set_jump_log2) = create_signal(10u32);
+    let (jump_error, set_jump_error) = create_signal(String::new());
+    let jump = move || {
+        if unbounded() {
+            set_jump_error(String::from("jumps don't work on an unbounded universe"));
+            return;
+        }
+        let mut result = Ok(());
+        w_board.update(|b| result = hashlife::jump(b, jump_log2()));
+        match result {
+            Ok(()) => {
+                r_board.with_untracked(|b| {
+                    set_timeline.update(|t| t.skip(&b.grid, 1 << jump_log2()))
+                });
+                set_jump_error(String::new());
+                render_board(
+                    canvas_ref.get().unwrap(),
+                    width(),
+                    height(),
+                    &viewport(),
+                    &r_board(),
+                );
+            }
+            Err(e) => set_jump_error(e),
+        }
+    };
+
     let timer_function = move |input: ev::Event| {
         set_delay(event_target_value(&input).parse().unwrap());
         if !paused() {
@@ -69,42 +211,215 @@ pub fn Settings(
 
     create_effect(move |_| {
         if let Some(h) = handle() {
-            if paused() {
+            if paused() || !active() {
                 h.unwrap().clear();
             }
         }
     });
 
-    let gen_rand = move || {
-        let mut grid = vec![vec![0; w()]; h()];
-        for i in 0..grid.len() {
-            for j in 0..grid[i].len() {
-                let rand = (random() * r_board().state_types.len() as f64) as usize;
-                grid[i][j] = rand;
+    // Carries on playing when the 2D mode comes back
+    create_effect(move |was_active: Option<bool>| {
+        let active = active();
+        if active && was_active == Some(false) && !paused.get_untracked() {
+            set_handle(Some(set_interval_with_handle(
+                update,
+                Duration::from_millis(delay.get_untracked()),
+            )));
+        }
+        active
+    });
+
+    let neighborhood_function = move |kind: String, radius: u32, custom: String| {
+        let neighborhood = match kind.as_str() {
+            "von_neumann" => Neighborhood::VonNeumann(radius),
+            "cross" => Neighborhood::Cross(radius),
+            "custom" => match parse_offsets(&custom) {
+                Some(offsets) => Neighborhood::Custom(offsets),
+                None => return,
+            },
+            _ => Neighborhood::Moore(radius),
+        };
+        if neighborhood.validate().is_err() {
+            return;
+        }
+        edit_board(r_board, w_board, history, |b| b.neighborhood = neighborhood);
+    };
+
+    let kind = move || neighborhood_kind(&r_board().neighborhood).to_string();
+    let radius = move || match r_board().neighborhood {
+        Neighborhood::Moore(r) | Neighborhood::VonNeumann(r) | Neighborhood::Cross(r) => r,
+        Neighborhood::Custom(_) => 1,
+    };
+    let custom = move || format_offsets(&r_board().neighborhood.offsets());
+
+    // Kept to the states there are, which may have shrunk since it was set
+    let (border_state, set_border_state) = create_signal(1usize);
+    let border_state = move || {
+        let last = r_board.with_untracked(|b| b.state_types.len().saturating_sub(1));
+        border_state().min(last)
+    };
+    let boundary = move |kind: String| match kind.as_str() {
+        "twist" => Boundary::Twist,
+        "reflect" => Boundary::Reflect,
+        "dead" => Boundary::Dead,
+        "state" => Boundary::State(border_state()),
+        _ => Boundary::Wrap,
+    };
+    let kind_x = move || boundary_kind(&r_board().boundary_x);
+    let kind_y = move || boundary_kind(&r_board().boundary_y);
+
+    let border_function = move |state: usize| {
+        set_border_state(state);
+        edit_board(r_board, w_board, history, |b| {
+            for edge in [&mut b.boundary_x, &mut b.boundary_y] {
+                if let Boundary::State(_) = edge {
+                    *edge = Boundary::State(state);
+                }
             }
+        });
+    };
+
+    // Only changes when the rules do, so typing into the box isn't overwritten every step
+    let rule_string = create_memo(move |_| {
+        r_board.with(|b| rulestring::format(&b.state_types, &b.neighborhood).unwrap_or_default())
+    });
+    // Read when loading, so it's whatever the box shows rather than what was last typed
+    let rule_ref: NodeRef<html::Input> = create_node_ref();
+    let (rule_error, set_rule_error) = create_signal(String::new());
+
+    let load_rule = move || match rulestring::parse(&rule_ref.get().unwrap().value()) {
+        Ok((states, neighborhood)) => {
+            // Batched, so the state editor is rebuilt before it sees fewer states
+            batch(|| {
+                edit_board(r_board, w_board, history, |b| {
+                    b.set_state_types(states);
+                    b.neighborhood = neighborhood;
+                });
+                set_states_revision.update(|r| *r += 1);
+            });
+            set_rule_error(String::new());
+            render_board(
+                canvas_ref.get().unwrap(),
+                width(),
+                height(),
+                &viewport(),
+                &r_board(),
+            );
         }
-        w_board.update(|b| b.grid = grid);
+        Err(e) => set_rule_error(e),
+    };
+
+    let gen_rand = move || {
+        let mut grid = r_board.with(|b| Grid::new(b.grid.width(), b.grid.height()));
+        for cell in grid.cells_mut() {
+            let rand = (random() * r_board().state_types.len() as f64) as Cell;
+            *cell = rand;
+        }
+        edit_board(r_board, w_board, history, |b| b.grid = grid);
     };
 
+    // Crops or pads the board, keeping the cells around the anchor
+    let (anchor, set_anchor) = create_signal(Anchor::Center);
+    let resize = move |w: usize, h: usize| {
+        let (w, h) = (w.clamp(1, MAX_SIZE), h.clamp(1, MAX_SIZE));
+        edit_board(r_board, w_board, history, |b| {
+            b.grid = b.grid.resized(w, h, anchor())
+        });
+        // The universe would otherwise still hold the cells where they were
+        if unbounded.get_untracked() {
+            restart_universe(true);
+        }
+        render_board(
+            canvas_ref.get().unwrap(),
+            width(),
+            height(),
+            &viewport(),
+            &r_board(),
+        );
+    };
+    // Starts the dice over for rules left to chance, so a run can be repeated
+    let (seed, set_seed) = create_signal(0u64);
+    let reseed = move || {
+        w_board.update(|b| b.reseed(seed()));
+        universe.update_value(|u| {
+            if let Some(u) = u {
+                u.random = Random::new(seed());
+            }
+        });
+    };
+
+    let board_width = move || r_board.with(|b| b.grid.width());
+    let board_height = move || r_board.with(|b| b.grid.height());
+
     view! {
-        <button on:click=move |_| {gen_rand(); render_board(canvas_ref.get().unwrap(), width(), height(), r_cell_size(), &r_board())}>Generate Random Board</button>
-        // <div>
-        //     Cell Size:
-        //     {r_cell_size}
-        //     <input
-        //         type="range"
-        //         value=move || r_cell_size()
-        //         min=32
-        //         max=128
-        //         step=32
-        //         on:input=move |ev| {
-        //             slider_function(ev);
-        //         }
-        //     />
-
-        // </div>
+        <button on:click=move |_| {gen_rand(); render_board(canvas_ref.get().unwrap(), width(), height(), &viewport(), &r_board())}>Generate Random Board</button>
+        <div>
+            "Board Size: "
+            <input
+                type="number"
+                min="1"
+                max=MAX_SIZE
+                prop:value=board_width
+                on:change=move |ev| {
+                    if let Ok(w) = event_target_value(&ev).parse::<usize>() {
+                        resize(w, board_height());
+                    }
+                }
+            />
+            " x "
+            <input
+                type="number"
+                min="1"
+                max=MAX_SIZE
+                prop:value=board_height
+                on:change=move |ev| {
+                    if let Ok(h) = event_target_value(&ev).parse::<usize>() {
+                        resize(board_width(), h);
+                    }
+                }
+            />
+            " Anchor: "
+            <select on:input=move |ev| {
+                set_anchor(
+                    match event_target_value(&ev).as_str() {
+                        "top_left" => Anchor::TopLeft,
+                        _ => Anchor::Center,
+                    },
+                )
+            }>
+                <option value="center" selected=move || anchor() == Anchor::Center>
+                    Center
+                </option>
+                <option value="top_left" selected=move || anchor() == Anchor::TopLeft>
+                    Top Left
+                </option>
+            </select>
+        </div>
 
         <div>
+            Cell Size:
+            {move || viewport().cell_size.round()}
+            <input
+                type="range"
+                prop:value=move || viewport().cell_size
+                min=viewport::MIN_CELL_SIZE
+                max=viewport::MAX_CELL_SIZE
+                on:input=move |ev| {
+                    slider_function(ev);
+                }
+            />
+
+        </div>
+
+        <div>
+            <input
+                value="Previous"
+                type="button"
+                disabled=move || timeline.with(|t| t.position() == t.first())
+                on:click=move |_| {
+                    seek(timeline.with(|t| t.position()) - 1);
+                }
+            />
             <input
                 value="Next"
                 type="button"
@@ -112,7 +427,152 @@ pub fn Settings(
                     update();
                 }
             />
+            " Generation "
+            {move || timeline.with(|t| t.position())}
+            <input
+                type="range"
+                min=move || timeline.with(|t| t.first())
+                max=move || timeline.with(|t| t.last())
+                prop:value=move || timeline.with(|t| t.position())
+                on:input=move |ev| {
+                    if let Ok(generation) = event_target_value(&ev).parse::<usize>() {
+                        seek(generation);
+                    }
+                }
+            />
+
+        </div>
 
+        <div>
+            "Jump 2^"
+            <input
+                type="number"
+                min="0"
+                max=MAX_JUMP
+                value=jump_log2
+                on:input=move |ev| {
+                    if let Ok(log2) = event_target_value(&ev).parse::<u32>() {
+                        set_jump_log2(log2.min(MAX_JUMP));
+                    }
+                }
+            />
+            " generations "
+            <button on:click=move |_| jump()>"Jump"</button>
+            " "
+            {jump_error}
+        </div>
+
+        <div>
+            "Seed "
+            <input
+                type="number"
+                min="0"
+                prop:value=seed
+                on:change=move |ev| {
+                    if let Ok(seed) = event_target_value(&ev).parse::<u64>() {
+                        set_seed(seed);
+                    }
+                }
+            />
+            <button on:click=move |_| reseed()>"Reseed"</button>
+        </div>
+
+        <div>
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=unbounded
+                    on:change=move |ev| {
+                        let on = event_target_checked(&ev);
+                        restart_universe(on);
+                        set_universe_error(String::new());
+                        set_unbounded(on);
+                    }
+                />
+                " Unbounded universe"
+            </label>
+            " "
+            {universe_error}
+        </div>
+
+        <div>
+            Rule:
+            <input
+                type="text"
+                placeholder="B3/S23"
+                prop:value=rule_string
+                node_ref=rule_ref
+            />
+            <button on:click=move |_| load_rule()>"Load Rule"</button>
+            " "
+            {rule_error}
+        </div>
+
+        <div>
+            Neighborhood:
+            <select on:input=move |ev| {
+                neighborhood_function(event_target_value(&ev), radius(), custom());
+            }>
+                <option value="moore" selected=move || kind() == "moore">Moore</option>
+                <option value="von_neumann" selected=move || kind() == "von_neumann">
+                    Von Neumann
+                </option>
+                <option value="cross" selected=move || kind() == "cross">Cross</option>
+                <option value="custom" selected=move || kind() == "custom">Custom</option>
+            </select>
+            " Radius: "
+            <input
+                type="number"
+                min="1"
+                max=MAX_RADIUS
+                value=radius
+                disabled=move || kind() == "custom"
+                on:input=move |ev| {
+                    if let Ok(r) = event_target_value(&ev).parse::<u32>() {
+                        neighborhood_function(kind(), r.clamp(1, MAX_RADIUS), custom());
+                    }
+                }
+            />
+            " Offsets (dx,dy): "
+            <input
+                type="text"
+                value=custom
+                disabled=move || kind() != "custom"
+                on:change=move |ev| {
+                    neighborhood_function(kind(), radius(), event_target_value(&ev));
+                }
+            />
+        </div>
+
+        <div>
+            "Edges: Horizontal "
+            <select on:input=move |ev| {
+                let edge = boundary(event_target_value(&ev));
+                edit_board(r_board, w_board, history, |b| b.boundary_x = edge);
+            }>
+                {boundary_options(kind_x)}
+            </select>
+            " Vertical "
+            <select on:input=move |ev| {
+                let edge = boundary(event_target_value(&ev));
+                edit_board(r_board, w_board, history, |b| b.boundary_y = edge);
+            }>
+                {boundary_options(kind_y)}
+            </select>
+            " Border State: "
+            <input
+                type="number"
+                min="0"
+                max=move || r_board().state_types.len() - 1
+                value=border_state
+                on:input=move |ev| {
+                    if let Ok(state) = event_target_value(&ev).parse::<usize>() {
+                        if state < r_board().state_types.len() {
+                            border_function(state);
+                        }
+                    }
+                }
+            />
         </div>
 
         <div>
@@ -147,3 +607,57 @@ pub fn Settings(
         <div></div>
     }
 }
+
+// The engine's limit, held down to what a generation number can count to,
+// which is 32 bits in the browser
+const MAX_JUMP: u32 = match hashlife::MAX_JUMP < usize::BITS - 2 {
+    true => hashlife::MAX_JUMP,
+    false => usize::BITS - 2,
+};
+
+fn boundary_options(kind: impl Fn() -> &'static str + Copy + 'static) -> impl IntoView {
+    view! {
+        <option value="wrap" selected=move || kind() == "wrap">Wrap</option>
+        <option value="twist" selected=move || kind() == "twist">Twisted Wrap</option>
+        <option value="reflect" selected=move || kind() == "reflect">Mirror</option>
+        <option value="dead" selected=move || kind() == "dead">Dead</option>
+        <option value="state" selected=move || kind() == "state">Border State</option>
+    }
+}
+
+fn boundary_kind(boundary: &Boundary) -> &'static str {
+    match boundary {
+        Boundary::Wrap => "wrap",
+        Boundary::Twist => "twist",
+        Boundary::Reflect => "reflect",
+        Boundary::Dead => "dead",
+        Boundary::State(_) => "state",
+    }
+}
+
+fn neighborhood_kind(neighborhood: &Neighborhood) -> &'static str {
+    match neighborhood {
+        Neighborhood::Moore(_) => "moore",
+        Neighborhood::VonNeumann(_) => "von_neumann",
+        Neighborhood::Cross(_) => "cross",
+        Neighborhood::Custom(_) => "custom",
+    }
+}
+
+// Offsets are written as space separated "dx,dy" pairs, e.g. "-1,0 1,0 0,-1 0,1"
+fn parse_offsets(text: &str) -> Option<Vec<(i32, i32)>> {
+    text.split_whitespace()
+        .map(|pair| {
+            let (dx, dy) = pair.split_once(',')?;
+            Some((dx.trim().parse().ok()?, dy.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn format_offsets(offsets: &[(i32, i32)]) -> String {
+    offsets
+        .iter()
+        .map(|(dx, dy)| format!("{},{}", dx, dy))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
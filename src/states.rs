@@ -1,20 +1,21 @@
+use crate::canvas::edit_board;
+use crate::history::History;
 use crate::life::*;
+use crate::viewport::Viewport;
 use js_sys::Math::random;
 use leptos::html::Canvas;
 use leptos::*;
-use std::rc::Rc;
-use wasm_bindgen::JsCast;
 #[component]
 pub fn States(
     canvas_ref: NodeRef<html::Canvas>,
     width: ReadSignal<i32>,
     height: ReadSignal<i32>,
-    r_cell_size: ReadSignal<i32>,
+    viewport: ReadSignal<Viewport>,
     r_board: ReadSignal<Board>,
     w_board: WriteSignal<Board>,
-    render_grid: fn(HtmlElement<Canvas>, i32, i32, i32),
-    render_board: fn(HtmlElement<Canvas>, i32, i32, i32, &Board),
+    render_board: fn(HtmlElement<Canvas>, i32, i32, &Viewport, &Board),
     set_state: WriteSignal<usize>,
+    history: StoredValue<History>,
 ) -> impl IntoView {
     let (next_id, set_next_id) = create_signal(r_board().state_types.len());
     let initial_states = (0..next_id())
@@ -23,8 +24,9 @@ pub fn States(
     let (states, set_states) = create_signal(initial_states);
 
     let add_states = move |_| {
+        let before = r_board.get_untracked();
         let sig = create_signal(next_id() + 1);
-        let random_color = format!("#{:X}", ((random() * 16777215.) as i32));
+        let random_color = format!("#{:06X}", ((random() * 16777215.) as i32));
         let default_state = State::new(
             next_id(),
             random_color,
@@ -32,15 +34,17 @@ pub fn States(
             vec![
                 Rule::new(
                     next_id(),
-                    (random() * 8. + 1.) as u32,
-                    (random() * r_board().state_types.len() as f64) as usize,
-                    Rc::new(|count, target| if count == target { true } else { false }),
+                    Condition::count(
+                        (random() * r_board().state_types.len() as f64) as usize,
+                        Predicate::Equal((random() * 8. + 1.) as u32),
+                    ),
                 ),
                 Rule::new(
                     next_id(),
-                    (random() * 8. + 1.) as u32,
-                    (random() * r_board().state_types.len() as f64) as usize,
-                    Rc::new(|count, target| if count == target { true } else { false }),
+                    Condition::count(
+                        (random() * r_board().state_types.len() as f64) as usize,
+                        Predicate::Equal((random() * 8. + 1.) as u32),
+                    ),
                 ),
             ],
         );
@@ -49,28 +53,20 @@ pub fn States(
             states.push((next_id(), sig));
         });
 
-        let w = move || (width() / r_cell_size()) as usize;
-        let h = move || (height() / r_cell_size()) as usize;
-
-        w_board.set(Board::new(w(), h(), Some(r_board().state_types.clone())));
-        let ctx = canvas_ref
-            .get()
-            .unwrap()
-            .get_context("2d")
-            .ok()
-            .flatten()
-            .expect("canvas to have context")
-            .unchecked_into::<web_sys::CanvasRenderingContext2d>();
-        ctx.set_fill_style(&wasm_bindgen::JsValue::from_str(
-            &r_board().state_types[0].color,
-        ));
-        ctx.fill_rect(0.0, 0.0, width() as f64, height() as f64);
-
-        render_grid(canvas_ref.get().unwrap(), width(), height(), r_cell_size());
+        w_board.update(|b| b.grid = Grid::new(b.grid.width(), b.grid.height()));
+        render_board(
+            canvas_ref.get().unwrap(),
+            width(),
+            height(),
+            &viewport(),
+            &r_board(),
+        );
         set_next_id.update(|a| *a += 1);
+        r_board.with_untracked(|after| history.update_value(|h| h.record(&before, after)));
     };
 
     let remove_states = move |id| {
+        let before = r_board.get_untracked();
         set_states.update(|states| {
             states.retain(|(state_id, _)| &(*state_id as i32) != &(id as i32));
         });
@@ -89,42 +85,36 @@ pub fn States(
         for i in 0..r_board().state_types.len() {
             for j in 0..r_board().state_types[i].rules.len() {
                 let rule = r_board().state_types[i].rules[j].clone();
-                if rule.count_state >= r_board().state_types.len() {
-                    w_board.update(|b| b.state_types[i].rules[j].count_state = 0);
-                }
+                w_board.update(|b| {
+                    let len = b.state_types.len();
+                    for state in b.state_types[i].rules[j].condition.count_states_mut() {
+                        if *state >= len {
+                            *state = 0;
+                        }
+                    }
+                });
                 if rule.target_state >= r_board().state_types.len() {
                     w_board.update(|b| b.state_types[i].rules[j].target_state = 0);
                 }
             }
         }
-        let w = move || (width() / r_cell_size()) as usize;
-        let h = move || (height() / r_cell_size()) as usize;
-
-        w_board.set(Board::new(
-            w() as usize,
-            h() as usize,
-            Some(r_board().state_types.clone()),
-        ));
-        let ctx = canvas_ref
-            .get()
-            .unwrap()
-            .get_context("2d")
-            .ok()
-            .flatten()
-            .expect("canvas to have context")
-            .unchecked_into::<web_sys::CanvasRenderingContext2d>();
-        ctx.clear_rect(0.0, 0.0, width() as f64, height() as f64);
-        if id == 0 {
-            render_board(
-                canvas_ref.get().unwrap(),
-                width(),
-                height(),
-                r_cell_size(),
-                &r_board(),
-            );
-        } else {
-            render_grid(canvas_ref.get().unwrap(), width(), height(), r_cell_size());
-        }
+        w_board.update(|b| {
+            let len = b.state_types.len();
+            for edge in [&mut b.boundary_x, &mut b.boundary_y] {
+                if matches!(edge, Boundary::State(state) if *state >= len) {
+                    *edge = Boundary::State(0);
+                }
+            }
+        });
+        w_board.update(|b| b.grid = Grid::new(b.grid.width(), b.grid.height()));
+        render_board(
+            canvas_ref.get().unwrap(),
+            width(),
+            height(),
+            &viewport(),
+            &r_board(),
+        );
+        r_board.with_untracked(|after| history.update_value(|h| h.record(&before, after)));
     };
 
     view! {
@@ -148,18 +138,23 @@ pub fn States(
                         Color:
                         <input
                             type="color"
-                            value=move || { let c = r_board().state_types[state_index].color.clone();
-                            c }
+                            value=move || {
+                                r_board.with(|b| {
+                                    b.state_types
+                                        .get(state_index)
+                                        .map(|s| s.color.clone())
+                                        .unwrap_or_default()
+                                })
+                            }
                             on:input=move |ev| {
-                                w_board
-                                    .update(|b| {
+                                edit_board(r_board, w_board, history, |b| {
                                         b.state_types[state_index].color = event_target_value(&ev);
                                     });
                                 render_board(
                                     canvas_ref.get().unwrap(),
                                     width(),
                                     height(),
-                                    r_cell_size(),
+                                    &viewport(),
                                     &r_board(),
                                 );
                             }
@@ -168,8 +163,7 @@ pub fn States(
                         Fail State:
                         <select
                             on:input=move |ev| {
-                                w_board
-                                    .update(|b| {
+                                edit_board(r_board, w_board, history, |b| {
                                         b
                                             .state_types[state_index]
                                             .fail_state = event_target_value(&ev)
@@ -185,8 +179,11 @@ pub fn States(
                                 view=move |(id, (_, _))| {
                                     view! {
                                         <option id=state_index value=id selected=move || {
-                                            let fail = r_board().state_types[state_index].fail_state;
-                                            fail == id
+                                            r_board.with(|b| {
+                                                b.state_types
+                                                    .get(state_index)
+                                                    .is_some_and(|s| s.fail_state == id)
+                                            })
                                         }>
                                             State
                                             {id}
@@ -196,6 +193,27 @@ pub fn States(
                             />
 
                         </select>
+                        " with chance "
+                        <input
+                            type="number"
+                            min="0"
+                            max="1"
+                            step="0.01"
+                            prop:value=move || {
+                                r_board.with(|b| {
+                                    b.state_types
+                                        .get(state_index)
+                                        .map_or(1.0, |s| s.fail_probability)
+                                })
+                            }
+                            on:change=move |ev| {
+                                if let Some(p) = parse_probability(&event_target_value(&ev)) {
+                                    edit_board(r_board, w_board, history, |b| {
+                                        b.state_types[state_index].fail_probability = p;
+                                    });
+                                }
+                            }
+                        />
 
                         <div>
 
@@ -209,20 +227,12 @@ pub fn States(
                                 let (rules, set_rules) = create_signal(initial_rules);
                                 let add_rule = move |_| {
                                     let sig = create_signal(next_rule() + 1);
-                                    w_board
-                                        .update(|board| {
+                                    edit_board(r_board, w_board, history, |board| {
                                             board
                                                 .state_types[state_index]
                                                 .rules
                                                 .push(
-                                                    Rule::new(
-                                                        next_rule(),
-                                                        3,
-                                                        1,
-                                                        Rc::new(|count, target_count| {
-                                                            if count == target_count { true } else { false }
-                                                        }),
-                                                    ),
+                                                    Rule::new(next_rule(), Condition::count(1, Predicate::Equal(3))),
                                                 )
                                         });
                                     set_rules
@@ -240,18 +250,10 @@ pub fn States(
                                         set_rules.update(|a| a[i].0 -= 1);
                                     }
                                     set_next_rule.update(|a| *a -= 1);
-                                    w_board
-                                        .update(|b| {
+                                    edit_board(r_board, w_board, history, |b| {
                                             b.state_types[state_index].rules.remove(id);
                                         });
                                 };
-                                let set_count = move |ev, state_index: usize, rule_id: usize| {
-                                    w_board
-                                        .update(|b| {
-                                            b.state_types[state_index].rules[rule_id].target_count = ev;
-                                        });
-                                };
-
                                 view! {
                                     Rules:
                                     <button on:click=add_rule>"Add Rule"</button>
@@ -262,65 +264,36 @@ pub fn States(
                                             view! {
                                                 <div></div>
                                                 "If "
-                                                <input
-                                                    value=move || {
-                                                        r_board()
-                                                            .state_types[state_index]
-                                                            .rules[rule_id]
-                                                            .target_count
-                                                    }
-
-                                                    type="number"
-                                                    size="1"
-                                                    min="0"
-                                                    max="8"
-                                                    on:input=move |ev| {
-                                                        set_count(
-                                                            event_target_value(&ev).parse().unwrap(),
-                                                            state_index,
-                                                            rule_id,
-                                                        );
-                                                    }
-                                                />
-
-                                                " neighbors of type "
-                                                <select
-                                                    on:input=move |ev| {
-                                                        let count = event_target_value(&ev).parse::<usize>().unwrap();
-                                                        w_board
-                                                            .update(|b| {
-                                                                b.state_types[state_index].rules[rule_id].count_state = count;
+                                                {
+                                                    let condition = create_memo(move |_| {
+                                                        r_board
+                                                            .with(|b| {
+                                                                let rule = b
+                                                                    .state_types
+                                                                    .get(state_index)
+                                                                    .and_then(|s| s.rules.get(rule_id));
+                                                                (rule.map(|r| r.condition.clone()), b.state_types.len())
+                                                            })
+                                                    });
+                                                    let edit = move |path: Vec<usize>, f: Box<dyn FnOnce(&mut Condition)>| {
+                                                        edit_board(r_board, w_board, history, |b| {
+                                                                let root = &mut b.state_types[state_index].rules[rule_id].condition;
+                                                                if let Some(node) = node_mut(root, &path) {
+                                                                    f(node);
+                                                                }
                                                             });
+                                                    };
+                                                    move || {
+                                                        let (condition, state_count) = condition();
+                                                        condition
+                                                            .map(|c| condition_view(&c, vec![], state_count, edit, false))
                                                     }
-                                                >
-                                                    <For
-                                                        each=states
-                                                        key=|states| states.0
-
-                                                        view=move |(id, (_, _))| {
-                                                            view! {
-                                                                <option
-                                                                    value=id
-                                                                    id=rule_id
-                                                                    selected=move || {
-                                                                        let count = r_board().state_types[state_index].rules[rule_id].count_state;
-                                                                        count == id
-                                                                    }
-                                                                >
-                                                                    State
-                                                                    {id}
-                                                                </option>
-                                                            }
-                                                        }
-                                                    />
-
-                                                </select>
+                                                }
                                                 " go to "
                                                 <select
                                                     on:input=move |ev| {
                                                         let target = event_target_value(&ev).parse::<usize>().unwrap();
-                                                        w_board
-                                                            .update(|b| {
+                                                        edit_board(r_board, w_board, history, |b| {
                                                                 b.state_types[state_index].rules[rule_id].target_state = target;
                                                             });
                                                     }
@@ -331,8 +304,12 @@ pub fn States(
                                                         view=move |(id, (_, _))| {
                                                             view! {
                                                                 <option id=rule_id value=id selected=move || {
-                                                                    let target = r_board().state_types[state_index].rules[rule_id].target_state;
-                                                                    target == id
+                                                                    r_board.with(|b| {
+                                                                        b.state_types
+                                                                            .get(state_index)
+                                                                            .and_then(|s| s.rules.get(rule_id))
+                                                                            .is_some_and(|r| r.target_state == id)
+                                                                    })
                                                                 }>
                                                                     State
                                                                     {id}
@@ -342,6 +319,30 @@ pub fn States(
                                                     />
 
                                                 </select>
+                                                " with chance "
+                                                <input
+                                                    type="number"
+                                                    min="0"
+                                                    max="1"
+                                                    step="0.01"
+                                                    prop:value=move || {
+                                                        r_board.with(|b| {
+                                                            b.state_types
+                                                                .get(state_index)
+                                                                .and_then(|s| s.rules.get(rule_id))
+                                                                .map_or(1.0, |r| r.probability)
+                                                        })
+                                                    }
+                                                    on:change=move |ev| {
+                                                        let text = event_target_value(&ev);
+                                                        if let Some(p) = parse_probability(&text) {
+                                                            edit_board(r_board, w_board, history, |b| {
+                                                                let rule = &mut b.state_types[state_index].rules[rule_id];
+                                                                rule.probability = p;
+                                                            });
+                                                        }
+                                                    }
+                                                />
 
                                                 <button on:click=move |_| {
                                                     remove_rule(rule_id)
@@ -359,3 +360,261 @@ pub fn States(
         />
     }
 }
+
+const CONDITION_KINDS: [(&str, &str); 4] = [
+    ("count", "neighbors"),
+    ("and", "all of"),
+    ("or", "any of"),
+    ("not", "not"),
+];
+
+fn condition_kind(condition: &Condition) -> &'static str {
+    match condition {
+        Condition::Count { .. } => "count",
+        Condition::And(_) => "and",
+        Condition::Or(_) => "or",
+        Condition::Not(_) => "not",
+    }
+}
+
+// Changes the kind of a node, keeping its children where the new kind has room for them
+fn convert(condition: Condition, kind: &str) -> Condition {
+    let default = || Condition::count(1, Predicate::Equal(3));
+    let children = match condition {
+        Condition::And(children) | Condition::Or(children) => children,
+        Condition::Not(child) => vec![*child],
+        leaf => vec![leaf],
+    };
+    match kind {
+        "and" => Condition::And(children),
+        "or" => Condition::Or(children),
+        "not" => Condition::Not(Box::new(
+            children.into_iter().next().unwrap_or_else(default),
+        )),
+        _ => children
+            .into_iter()
+            .find(|c| matches!(c, Condition::Count { .. }))
+            .unwrap_or_else(default),
+    }
+}
+
+// Follows a path of child indices down from the root; the child of a Not is index 0
+fn node_mut<'a>(condition: &'a mut Condition, path: &[usize]) -> Option<&'a mut Condition> {
+    match path.split_first() {
+        None => Some(condition),
+        Some((&i, rest)) => match condition {
+            Condition::And(children) | Condition::Or(children) => {
+                node_mut(children.get_mut(i)?, rest)
+            }
+            Condition::Not(child) if i == 0 => node_mut(child, rest),
+            _ => None,
+        },
+    }
+}
+
+type Edit = Box<dyn FnOnce(&mut Condition)>;
+
+// Renders the node of a rule's condition tree found at path. Every control hands
+// edit the path of the node it changes and a function that changes it.
+fn condition_view(
+    condition: &Condition,
+    path: Vec<usize>,
+    state_count: usize,
+    edit: impl Fn(Vec<usize>, Edit) + Copy + 'static,
+    removable: bool,
+) -> View {
+    let kind = condition_kind(condition);
+    let kind_path = path.clone();
+    let kind_select = view! {
+        <select on:input=move |ev| {
+            let kind = event_target_value(&ev);
+            edit(
+                kind_path.clone(),
+                Box::new(move |node: &mut Condition| *node = convert(node.clone(), &kind)),
+            );
+        }>
+            {CONDITION_KINDS
+                .iter()
+                .map(|&(value, label)| {
+                    view! { <option value=value selected={value == kind}>{label}</option> }
+                })
+                .collect_view()}
+        </select>
+    };
+    let remove_path = path.clone();
+    let remove_button = removable.then(|| {
+        view! {
+            <button on:click=move |_| {
+                let (&index, parent) = remove_path.split_last().unwrap();
+                edit(parent.to_vec(), Box::new(move |node: &mut Condition| {
+                    if let Condition::And(children) | Condition::Or(children) = node {
+                        children.remove(index);
+                    }
+                }));
+            }>"Remove Condition"</button>
+        }
+    });
+    let child_view = |child: &Condition, index: usize, removable: bool| {
+        let mut child_path = path.clone();
+        child_path.push(index);
+        view! {
+            <div style="margin-left: 2em">
+                {condition_view(child, child_path, state_count, edit, removable)}
+            </div>
+        }
+    };
+
+    match condition {
+        Condition::Count { state, predicate } => {
+            let (state, kind) = (*state, predicate_kind(predicate));
+            let current = operand(predicate);
+            let (state_path, kind_path, operand_path) = (path.clone(), path.clone(), path.clone());
+            let set_predicate = move |path: Vec<usize>, kind: String, operand: String| {
+                if let Some(new) = parse_predicate(&kind, &operand) {
+                    edit(
+                        path,
+                        Box::new(move |node: &mut Condition| {
+                            if let Condition::Count { predicate, .. } = node {
+                                *predicate = new;
+                            }
+                        }),
+                    );
+                }
+            };
+            view! {
+                {kind_select}
+                " of type "
+                <select on:input=move |ev| {
+                    let count = event_target_value(&ev).parse::<usize>().unwrap();
+                    edit(state_path.clone(), Box::new(move |node: &mut Condition| {
+                        if let Condition::Count { state, .. } = node {
+                            *state = count;
+                        }
+                    }));
+                }>
+                    {(0..state_count)
+                        .map(|id| view! { <option value=id selected={id == state}>State {id}</option> })
+                        .collect_view()}
+                </select>
+                " number "
+                <select on:input=move |ev| {
+                    set_predicate(kind_path.clone(), event_target_value(&ev), current.clone());
+                }>
+                    {PREDICATE_KINDS
+                        .iter()
+                        .map(|&(value, label)| {
+                            view! { <option value=value selected={value == kind}>{label}</option> }
+                        })
+                        .collect_view()}
+                </select>
+                <input
+                    type="text"
+                    size="4"
+                    value=operand(predicate)
+                    on:change=move |ev| {
+                        set_predicate(operand_path.clone(), kind.to_string(), event_target_value(&ev));
+                    }
+                />
+                {remove_button}
+            }
+            .into_view()
+        }
+        Condition::And(children) | Condition::Or(children) => {
+            let add_path = path.clone();
+            view! {
+                {kind_select}
+                {remove_button}
+                {children
+                    .iter()
+                    .enumerate()
+                    .map(|(i, child)| child_view(child, i, true))
+                    .collect_view()}
+                <div style="margin-left: 2em">
+                    <button on:click=move |_| {
+                        edit(add_path.clone(), Box::new(|node: &mut Condition| {
+                            if let Condition::And(children) | Condition::Or(children) = node {
+                                children.push(Condition::count(1, Predicate::Equal(3)));
+                            }
+                        }));
+                    }>"Add Condition"</button>
+                </div>
+            }
+            .into_view()
+        }
+        Condition::Not(child) => view! {
+            {kind_select}
+            {remove_button}
+            {child_view(child, 0, false)}
+        }
+        .into_view(),
+    }
+}
+
+const PREDICATE_KINDS: [(&str, &str); 8] = [
+    ("eq", "=="),
+    ("ne", "!="),
+    ("lt", "<"),
+    ("le", "<="),
+    ("gt", ">"),
+    ("ge", ">="),
+    ("range", "in range"),
+    ("set", "in set"),
+];
+
+fn predicate_kind(predicate: &Predicate) -> &'static str {
+    match predicate {
+        Predicate::Equal(_) => "eq",
+        Predicate::NotEqual(_) => "ne",
+        Predicate::Less(_) => "lt",
+        Predicate::LessEqual(_) => "le",
+        Predicate::Greater(_) => "gt",
+        Predicate::GreaterEqual(_) => "ge",
+        Predicate::InRange(_, _) => "range",
+        Predicate::InSet(_) => "set",
+    }
+}
+
+// Ranges are written "2..3" and sets "2,3,5"
+fn operand(predicate: &Predicate) -> String {
+    match predicate {
+        Predicate::Equal(n)
+        | Predicate::NotEqual(n)
+        | Predicate::Less(n)
+        | Predicate::LessEqual(n)
+        | Predicate::Greater(n)
+        | Predicate::GreaterEqual(n) => n.to_string(),
+        Predicate::InRange(min, max) => format!("{}..{}", min, max),
+        Predicate::InSet(set) => set
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(","),
+    }
+}
+
+// A chance typed in as a number from 0 to 1
+fn parse_probability(text: &str) -> Option<f64> {
+    let p = text.trim().parse::<f64>().ok()?;
+    (0.0..=1.0).contains(&p).then_some(p)
+}
+
+// Reads every number out of the operand text, so switching between kinds keeps
+// as much of the old operand as makes sense
+fn parse_predicate(kind: &str, operand: &str) -> Option<Predicate> {
+    let numbers = operand
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let first = *numbers.first()?;
+    Some(match kind {
+        "ne" => Predicate::NotEqual(first),
+        "lt" => Predicate::Less(first),
+        "le" => Predicate::LessEqual(first),
+        "gt" => Predicate::Greater(first),
+        "ge" => Predicate::GreaterEqual(first),
+        "range" => Predicate::InRange(first, *numbers.last()?),
+        "set" => Predicate::InSet(numbers),
+        _ => Predicate::Equal(first),
+    })
+}